@@ -0,0 +1,163 @@
+use super::bson_reader::{self, Block};
+use super::{CollectionAnalyzer, Document};
+use failure::format_err;
+use std::io::Read;
+
+// First four bytes of every archive written by `mongodump --archive`.
+static ARCHIVE_MAGIC: u32 = 0x8199_e26d;
+
+impl CollectionAnalyzer {
+  /// Writes every collection of an archive written by `mongodump --archive`
+  /// in a single pass. Each namespace, i.e. `fanclub.members`, gets its own
+  /// SchemaParser, including collections without any documents.
+  ///
+  /// The archive is laid out as a prelude -- the archive's header and a
+  /// metadata document per collection -- followed by blocks of documents,
+  /// each starting with a header naming their namespace. Archives written
+  /// with `--gzip` need to be decompressed first.
+  ///
+  /// # Arguments
+  /// * `reader` - Anything implementing `io::Read`, i.e. a `BufReader` of a
+  /// file.
+  ///
+  /// # Examples
+  /// ```no_run
+  /// use mongodb_schema_parser::CollectionAnalyzer;
+  /// use std::fs::File;
+  /// use std::io::BufReader;
+  ///
+  /// let file = File::open("fanclub.archive").unwrap();
+  /// let mut analyzer = CollectionAnalyzer::new();
+  /// analyzer.write_archive(BufReader::new(file)).unwrap();
+  /// let result = analyzer.flush();
+  /// ```
+  pub fn write_archive<R: Read>(
+    &mut self,
+    mut reader: R,
+  ) -> Result<(), failure::Error> {
+    let mut buf = Vec::new();
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if u32::from_le_bytes(magic) != ARCHIVE_MAGIC {
+      return Err(format_err!("Not a mongodump archive"));
+    }
+
+    // prelude: the archive's header, then a metadata document per collection
+    match bson_reader::read_block(&mut reader, &mut buf)? {
+      Some(Block::Document(_)) => (),
+      _ => return Err(format_err!("Missing mongodump archive header")),
+    }
+    loop {
+      match bson_reader::read_block(&mut reader, &mut buf)? {
+        Some(Block::Document(metadata)) => {
+          self.collection(&Self::get_namespace(&metadata)?);
+        }
+        Some(Block::Terminator) => break,
+        None => return Err(format_err!("Unexpected end of mongodump archive")),
+      }
+    }
+
+    // body: blocks of a namespace header followed by that namespace's
+    // documents; once a namespace is done, its header has `EOF` set and no
+    // documents follow.
+    loop {
+      let header = match bson_reader::read_block(&mut reader, &mut buf)? {
+        Some(Block::Document(header)) => header,
+        Some(Block::Terminator) => {
+          return Err(format_err!("Missing mongodump archive namespace header"))
+        }
+        None => return Ok(()),
+      };
+      let schema_parser = self.collection(&Self::get_namespace(&header)?);
+      loop {
+        match bson_reader::read_block(&mut reader, &mut buf)? {
+          Some(Block::Document(doc)) => schema_parser.write_document(doc),
+          Some(Block::Terminator) => break,
+          None => {
+            return Err(format_err!("Unexpected end of mongodump archive"))
+          }
+        }
+      }
+    }
+  }
+
+  fn get_namespace(doc: &Document) -> Result<String, failure::Error> {
+    match (doc.get_str("db"), doc.get_str("collection")) {
+      (Ok(db), Ok(collection)) => Ok(format!("{}.{}", db, collection)),
+      _ => Err(format_err!("Missing namespace in mongodump archive")),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use bson::{bson, doc, encode_document};
+
+  static TERMINATOR: [u8; 4] = [0xff, 0xff, 0xff, 0xff];
+
+  fn write(bytes: &mut Vec<u8>, doc: Document) {
+    encode_document(bytes, &doc).unwrap();
+  }
+
+  fn archive() -> Vec<u8> {
+    let mut bytes = ARCHIVE_MAGIC.to_le_bytes().to_vec();
+    write(
+      &mut bytes,
+      doc! { "concurrent_collections": 4, "version": "0.1" },
+    );
+    for collection in &["members", "orders", "logs"] {
+      let metadata = doc! {
+        "db": "fanclub",
+        "collection": collection.to_string(),
+        "metadata": ""
+      };
+      write(&mut bytes, metadata);
+    }
+    bytes.extend_from_slice(&TERMINATOR);
+
+    let members = doc! { "db": "fanclub", "collection": "members" };
+    let orders = doc! { "db": "fanclub", "collection": "orders" };
+    write(&mut bytes, members.clone());
+    write(&mut bytes, doc! { "_id": 1, "name": "Ellie" });
+    bytes.extend_from_slice(&TERMINATOR);
+    write(&mut bytes, orders.clone());
+    write(&mut bytes, doc! { "member_id": 1 });
+    bytes.extend_from_slice(&TERMINATOR);
+    write(&mut bytes, members.clone());
+    write(&mut bytes, doc! { "_id": 2, "name": "Ava" });
+    bytes.extend_from_slice(&TERMINATOR);
+    for mut header in [members, orders] {
+      header.insert("EOF", true);
+      write(&mut bytes, header);
+      bytes.extend_from_slice(&TERMINATOR);
+    }
+    bytes
+  }
+
+  #[test]
+  fn it_writes_archive() {
+    let mut analyzer = CollectionAnalyzer::new();
+    analyzer.write_archive(archive().as_slice()).unwrap();
+    assert_eq!(analyzer.collections.len(), 3);
+    assert_eq!(analyzer.collections["fanclub.members"].count, 2);
+    assert_eq!(analyzer.collections["fanclub.orders"].count, 1);
+    assert_eq!(analyzer.collections["fanclub.logs"].count, 0);
+  }
+
+  #[test]
+  fn it_fails_on_invalid_magic() {
+    let mut bytes = archive();
+    bytes[0] = 0;
+    let mut analyzer = CollectionAnalyzer::new();
+    assert!(analyzer.write_archive(bytes.as_slice()).is_err());
+  }
+
+  #[test]
+  fn it_fails_on_truncated_archive() {
+    let bytes = archive();
+    let mut analyzer = CollectionAnalyzer::new();
+    let truncated = &bytes[..bytes.len() - 6];
+    assert!(analyzer.write_archive(truncated).is_err());
+  }
+}
@@ -0,0 +1,226 @@
+use super::{decode_document, Document, SchemaParser};
+use failure::format_err;
+use std::io::{self, Read};
+
+// MongoDB documents are at most 16 MiB, plus some room for the internal
+// documents mongodump writes to archives.
+const MAX_DOCUMENT_SIZE: i32 = 16 * 1024 * 1024 + 16 * 1024;
+
+/// Iterates over a stream of length-prefixed Bson documents, i.e. a `.bson`
+/// file written by `mongodump`. Iteration stops after the first error, as
+/// the position of the next document can't be known.
+///
+/// Every document takes two reads, so a file should be wrapped in a
+/// `BufReader`.
+///
+/// # Examples
+/// ```no_run
+/// use mongodb_schema_parser::BsonReader;
+/// use std::fs::File;
+/// use std::io::BufReader;
+///
+/// let file = BufReader::new(File::open("dump/fanclub/members.bson").unwrap());
+/// for doc in BsonReader::new(file) {
+///   println!("{:?}", doc.unwrap());
+/// }
+/// ```
+pub struct BsonReader<R> {
+  reader: R,
+  buf: Vec<u8>,
+  done: bool,
+}
+
+impl<R: Read> BsonReader<R> {
+  pub fn new(reader: R) -> Self {
+    BsonReader {
+      reader,
+      buf: Vec::new(),
+      done: false,
+    }
+  }
+
+  fn read_document(&mut self) -> Result<Option<Document>, failure::Error> {
+    match read_block(&mut self.reader, &mut self.buf)? {
+      Some(Block::Document(doc)) => Ok(Some(doc)),
+      Some(Block::Terminator) => {
+        Err(format_err!("Invalid Bson document length: -1"))
+      }
+      None => Ok(None),
+    }
+  }
+}
+
+// A length-prefixed Bson document, or the `-1` length that mongodump's
+// archive format uses to terminate a sequence of documents.
+pub(crate) enum Block {
+  Document(Document),
+  Terminator,
+}
+
+// Reads the next block of a stream, using `buf` to hold the document's bytes.
+// Returns `None` when the stream ends in between blocks.
+pub(crate) fn read_block<R: Read>(
+  reader: &mut R,
+  buf: &mut Vec<u8>,
+) -> Result<Option<Block>, failure::Error> {
+  let mut len_bytes = [0u8; 4];
+  match read_until_full(reader, &mut len_bytes)? {
+    0 => return Ok(None),
+    4 => (),
+    _ => return Err(format_err!("Unexpected end of Bson stream")),
+  }
+  // the length includes the length prefix itself and the trailing null byte
+  let len = i32::from_le_bytes(len_bytes);
+  if len == -1 {
+    return Ok(Some(Block::Terminator));
+  }
+  // checked before allocating, as a corrupt length could be up to 2 GiB
+  if !(5..=MAX_DOCUMENT_SIZE).contains(&len) {
+    return Err(format_err!("Invalid Bson document length: {}", len));
+  }
+  buf.clear();
+  buf.extend_from_slice(&len_bytes);
+  buf.resize(len as usize, 0);
+  reader.read_exact(&mut buf[4..])?;
+  let mut slice: &[u8] = &buf;
+  Ok(Some(Block::Document(decode_document(&mut slice)?)))
+}
+
+impl<R: Read> Iterator for BsonReader<R> {
+  type Item = Result<Document, failure::Error>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.done {
+      return None;
+    }
+    let doc = self.read_document().transpose();
+    self.done = !matches!(doc, Some(Ok(_)));
+    doc
+  }
+}
+
+impl SchemaParser {
+  /// Writes every document of a stream of length-prefixed Bson documents,
+  /// i.e. a `.bson` file written by `mongodump`, to SchemaParser's fields
+  /// vector. Unlike converting the dump to json first, this keeps all Bson
+  /// types, such as Int and Long or Decimal128.
+  ///
+  /// # Arguments
+  /// * `reader` - Anything implementing `io::Read`, i.e. a `BufReader` of a
+  /// file.
+  ///
+  /// # Examples
+  /// ```no_run
+  /// use mongodb_schema_parser::SchemaParser;
+  /// use std::fs::File;
+  /// use std::io::BufReader;
+  ///
+  /// let file = File::open("dump/fanclub/members.bson").unwrap();
+  /// let mut schema_parser = SchemaParser::new();
+  /// schema_parser.write_bson_reader(BufReader::new(file)).unwrap();
+  /// let schema = schema_parser.flush();
+  /// ```
+  pub fn write_bson_reader<R: Read>(
+    &mut self,
+    reader: R,
+  ) -> Result<(), failure::Error> {
+    for doc in BsonReader::new(reader) {
+      self.write_document(doc?);
+    }
+    Ok(())
+  }
+}
+
+// Like `read_exact`, but returns how many bytes were read when the reader
+// ends early.
+fn read_until_full<R: Read>(
+  reader: &mut R,
+  buf: &mut [u8],
+) -> io::Result<usize> {
+  let mut read = 0;
+  while read < buf.len() {
+    match reader.read(&mut buf[read..]) {
+      Ok(0) => break,
+      Ok(n) => read += n,
+      Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+      Err(e) => return Err(e),
+    }
+  }
+  Ok(read)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use bson::{bson, doc, encode_document};
+
+  fn dump() -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for doc in &[
+      doc! { "name": "Nori", "age": 3_i64 },
+      doc! { "name": "Rey" },
+      doc! { "name": "Chashu", "age": 4 },
+    ] {
+      encode_document(&mut bytes, doc).unwrap();
+    }
+    bytes
+  }
+
+  #[test]
+  fn it_reads_documents() {
+    let docs: Vec<Document> = BsonReader::new(dump().as_slice())
+      .collect::<Result<_, _>>()
+      .unwrap();
+    assert_eq!(docs.len(), 3);
+    assert_eq!(docs[1].get_str("name").unwrap(), "Rey");
+  }
+
+  #[test]
+  fn it_reads_empty_stream() {
+    let empty: &[u8] = &[];
+    assert_eq!(BsonReader::new(empty).count(), 0);
+  }
+
+  #[test]
+  fn it_fails_on_truncated_stream() {
+    let bytes = dump();
+    let mut reader = BsonReader::new(&bytes[..bytes.len() - 3]);
+    assert!(reader.next().unwrap().is_ok());
+    assert!(reader.next().unwrap().is_ok());
+    assert!(reader.next().unwrap().is_err());
+    assert!(reader.next().is_none());
+
+    let mut reader = BsonReader::new(&bytes[..2]);
+    assert!(reader.next().unwrap().is_err());
+  }
+
+  #[test]
+  fn it_fails_on_invalid_length() {
+    let bytes: [u8; 5] = [2, 0, 0, 0, 0];
+    let mut reader = BsonReader::new(&bytes[..]);
+    assert!(reader.next().unwrap().is_err());
+    assert!(reader.next().is_none());
+  }
+
+  #[test]
+  fn it_fails_on_huge_length() {
+    let mut bytes = i32::MAX.to_le_bytes().to_vec();
+    bytes.extend_from_slice(&[0; 16]);
+    let mut reader = BsonReader::new(bytes.as_slice());
+    let error = reader.next().unwrap().unwrap_err();
+    assert_eq!(
+      error.to_string(),
+      "Invalid Bson document length: 2147483647"
+    );
+    assert!(reader.next().is_none());
+  }
+
+  #[test]
+  fn it_writes_bson_reader() {
+    let mut schema_parser = SchemaParser::new();
+    schema_parser.write_bson_reader(dump().as_slice()).unwrap();
+    assert_eq!(schema_parser.count, 3);
+    let age = &schema_parser.fields["age"];
+    assert_eq!(age.bson_types, vec!["Long", "Int"]);
+  }
+}
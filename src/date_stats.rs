@@ -0,0 +1,116 @@
+use chrono::{DateTime, Utc};
+use std::cmp;
+use std::collections::BTreeMap;
+
+/// Granularity of the histogram of dates, see
+/// `SchemaOptions::date_histogram`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum DateBucket {
+  Day,
+  Month,
+  Year,
+}
+
+impl DateBucket {
+  // Bucket keys sort chronologically, e.g. `2014-01` before `2014-02`.
+  fn format(self) -> &'static str {
+    match self {
+      DateBucket::Day => "%Y-%m-%d",
+      DateBucket::Month => "%Y-%m",
+      DateBucket::Year => "%Y",
+    }
+  }
+}
+
+/// Range of the dates seen for a UtcDatetime field type and, when enabled,
+/// the number of dates in each day, month or year.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DateStats {
+  pub min: DateTime<Utc>,
+  pub max: DateTime<Utc>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub histogram: Option<BTreeMap<String, usize>>,
+  #[serde(default, skip_serializing_if = "crate::state::skip")]
+  bucket: Option<DateBucket>,
+}
+
+impl DateStats {
+  pub fn new(date: DateTime<Utc>, bucket: Option<DateBucket>) -> Self {
+    let mut stats = DateStats {
+      min: date,
+      max: date,
+      histogram: bucket.map(|_| BTreeMap::new()),
+      bucket,
+    };
+    stats.update(date);
+    stats
+  }
+
+  pub fn update(&mut self, date: DateTime<Utc>) {
+    self.min = cmp::min(self.min, date);
+    self.max = cmp::max(self.max, date);
+    if let (Some(histogram), Some(bucket)) = (&mut self.histogram, self.bucket)
+    {
+      let key = date.format(bucket.format()).to_string();
+      *histogram.entry(key).or_insert(0) += 1;
+    }
+  }
+
+  pub fn merge(&mut self, other: &DateStats) {
+    self.min = cmp::min(self.min, other.min);
+    self.max = cmp::max(self.max, other.max);
+    if let (Some(histogram), Some(other_histogram)) =
+      (&mut self.histogram, &other.histogram)
+    {
+      for (key, count) in other_histogram {
+        *histogram.entry(key.to_string()).or_insert(0) += count;
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn date(date: &str) -> DateTime<Utc> {
+    date.parse().unwrap()
+  }
+
+  #[test]
+  fn it_creates_new() {
+    let stats = DateStats::new(date("2014-01-31T22:26:33Z"), None);
+    assert_eq!(stats.min, date("2014-01-31T22:26:33Z"));
+    assert_eq!(stats.max, date("2014-01-31T22:26:33Z"));
+    assert!(stats.histogram.is_none());
+  }
+
+  #[test]
+  fn it_updates() {
+    let mut stats =
+      DateStats::new(date("2014-01-31T22:26:33Z"), Some(DateBucket::Month));
+    stats.update(date("2012-09-14T14:44:21Z"));
+    stats.update(date("2014-01-02T02:39:24Z"));
+    assert_eq!(stats.min, date("2012-09-14T14:44:21Z"));
+    assert_eq!(stats.max, date("2014-01-31T22:26:33Z"));
+    let histogram = stats.histogram.unwrap();
+    assert_eq!(histogram.len(), 2);
+    assert_eq!(histogram["2012-09"], 1);
+    assert_eq!(histogram["2014-01"], 2);
+  }
+
+  #[test]
+  fn it_merges() {
+    let mut stats =
+      DateStats::new(date("2014-01-31T22:26:33Z"), Some(DateBucket::Year));
+    let mut other =
+      DateStats::new(date("2015-05-12T02:39:24Z"), Some(DateBucket::Year));
+    other.update(date("2014-06-22T13:19:16Z"));
+    stats.merge(&other);
+    assert_eq!(stats.min, date("2014-01-31T22:26:33Z"));
+    assert_eq!(stats.max, date("2015-05-12T02:39:24Z"));
+    let histogram = stats.histogram.unwrap();
+    assert_eq!(histogram["2014"], 2);
+    assert_eq!(histogram["2015"], 1);
+  }
+}
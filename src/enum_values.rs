@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+
+/// Counts distinct string values exactly, as long as there are at most
+/// `max_values` of them. A field with more distinct values can't be an enum,
+/// so counting stops once that number is exceeded.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct EnumCounter {
+  max_values: usize,
+  counts: BTreeMap<String, usize>,
+  overflowed: bool,
+}
+
+impl EnumCounter {
+  pub fn new(max_values: usize) -> Self {
+    EnumCounter {
+      max_values,
+      counts: BTreeMap::new(),
+      overflowed: false,
+    }
+  }
+
+  pub fn insert(&mut self, value: &str) {
+    if self.overflowed {
+      return;
+    }
+    match self.counts.get_mut(value) {
+      Some(count) => *count += 1,
+      None => {
+        self.counts.insert(value.to_string(), 1);
+        self.check_overflow();
+      }
+    }
+  }
+
+  pub fn merge(&mut self, other: &EnumCounter) {
+    self.overflowed |= other.overflowed;
+    if self.overflowed {
+      return;
+    }
+    for (value, count) in &other.counts {
+      *self.counts.entry(value.to_string()).or_insert(0) += count;
+    }
+    self.check_overflow();
+  }
+
+  /// Observed values and their counts, if there are few enough of them
+  /// relative to `total`, the number of values seen, to be an enum.
+  pub fn get_enum(
+    &self,
+    total: usize,
+    max_ratio: f32,
+  ) -> Option<BTreeMap<String, usize>> {
+    if self.overflowed || self.counts.is_empty() {
+      return None;
+    }
+    let ratio = self.counts.len() as f32 / total as f32;
+    if ratio <= max_ratio {
+      Some(self.counts.clone())
+    } else {
+      None
+    }
+  }
+
+  fn check_overflow(&mut self) {
+    if self.counts.len() > self.max_values {
+      self.overflowed = true;
+      self.counts.clear();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn it_gets_enum() {
+    let mut counter = EnumCounter::new(3);
+    for value in &["ACTIVE", "PENDING", "ACTIVE", "ACTIVE", "INACTIVE"] {
+      counter.insert(value);
+    }
+    let values = counter.get_enum(5, 0.6).unwrap();
+    assert_eq!(values.len(), 3);
+    assert_eq!(values["ACTIVE"], 3);
+    assert_eq!(values["PENDING"], 1);
+  }
+
+  #[test]
+  fn it_gets_no_enum_over_ratio() {
+    let mut counter = EnumCounter::new(3);
+    counter.insert("ACTIVE");
+    counter.insert("PENDING");
+    assert_eq!(counter.get_enum(2, 0.5), None);
+  }
+
+  #[test]
+  fn it_overflows() {
+    let mut counter = EnumCounter::new(2);
+    for value in &["a", "b", "c", "a"] {
+      counter.insert(value);
+    }
+    assert_eq!(counter.get_enum(100, 1.0), None);
+  }
+
+  #[test]
+  fn it_merges() {
+    let mut counter = EnumCounter::new(2);
+    let mut other = EnumCounter::new(2);
+    counter.insert("male");
+    other.insert("female");
+    other.insert("male");
+    counter.merge(&other);
+    let values = counter.get_enum(3, 1.0).unwrap();
+    assert_eq!(values["male"], 2);
+    assert_eq!(values["female"], 1);
+
+    other.insert("other");
+    counter.merge(&other);
+    assert_eq!(counter.get_enum(6, 1.0), None);
+  }
+}
@@ -0,0 +1,385 @@
+use bson::decimal128::Decimal128;
+use bson::oid::ObjectId;
+use bson::spec::BinarySubtype;
+use bson::{Bson, Document};
+use chrono::{DateTime, TimeZone, Utc};
+use failure::format_err;
+use serde_json::{Map, Number, Value};
+use std::convert::TryFrom;
+
+pub static MIN_KEY: &str = "$minKey";
+pub static MAX_KEY: &str = "$maxKey";
+
+/// Converts MongoDB Extended JSON v2, in either its canonical or its relaxed
+/// form, to Bson. Legacy forms written by older versions of `mongoexport`,
+/// i.e. `{ "$date": 1356351330000 }`, are accepted as well.
+///
+/// Bson has no MinKey and MaxKey values, so they are kept as
+/// `{ "$minKey": 1 }` and `{ "$maxKey": 1 }` documents; see `get_key_type`.
+pub fn to_bson(value: Value) -> Result<Bson, failure::Error> {
+  let bson = match value {
+    Value::Null => Bson::Null,
+    Value::Bool(boolean) => Bson::Boolean(boolean),
+    Value::Number(num) => to_number(&num),
+    Value::String(string) => Bson::String(string),
+    Value::Array(arr) => {
+      Bson::Array(arr.into_iter().map(to_bson).collect::<Result<_, _>>()?)
+    }
+    Value::Object(map) => match from_extended(&map)? {
+      Some(bson) => bson,
+      None => Bson::Document(to_document(map)?),
+    },
+  };
+  Ok(bson)
+}
+
+/// Returns `$minKey` or `$maxKey` for documents standing in for these
+/// values.
+pub fn get_key_type(doc: &Document) -> Option<&'static str> {
+  if doc.len() != 1 {
+    return None;
+  }
+  [MIN_KEY, MAX_KEY]
+    .iter()
+    .find(|key| doc.contains_key(key))
+    .copied()
+}
+
+fn to_document(map: Map<String, Value>) -> Result<Document, failure::Error> {
+  let mut doc = Document::new();
+  for (key, value) in map {
+    doc.insert(key, to_bson(value)?);
+  }
+  Ok(doc)
+}
+
+// Relaxed form numbers: integers are Int if they fit, Long otherwise.
+fn to_number(num: &Number) -> Bson {
+  match num.as_i64() {
+    Some(num) => match i32::try_from(num) {
+      Ok(num) => Bson::I32(num),
+      Err(_) => Bson::I64(num),
+    },
+    None => Bson::FloatingPoint(num.as_f64().unwrap_or(f64::NAN)),
+  }
+}
+
+// Returns `None` for documents that aren't an Extended JSON value, and an
+// error for Extended JSON values with invalid content.
+fn from_extended(
+  map: &Map<String, Value>,
+) -> Result<Option<Bson>, failure::Error> {
+  let bson = match map.len() {
+    1 => {
+      let (key, value) = map.iter().next().unwrap();
+      from_extended_value(key, value)?
+    }
+    2 => from_extended_pair(map)?,
+    _ => None,
+  };
+  Ok(bson)
+}
+
+fn from_extended_value(
+  key: &str,
+  value: &Value,
+) -> Result<Option<Bson>, failure::Error> {
+  let invalid = || format_err!("Invalid Extended JSON value for {}", key);
+  let bson = match (key, value) {
+    ("$oid", Value::String(oid)) => Bson::ObjectId(ObjectId::with_string(oid)?),
+    ("$symbol", Value::String(symbol)) => Bson::Symbol(symbol.to_string()),
+    ("$code", Value::String(code)) => Bson::JavaScriptCode(code.to_string()),
+    ("$numberInt", Value::String(num)) => Bson::I32(num.parse()?),
+    ("$numberLong", Value::String(num)) => Bson::I64(num.parse()?),
+    ("$numberDouble", Value::String(num)) => {
+      Bson::FloatingPoint(parse_double(num)?)
+    }
+    ("$numberDecimal", Value::String(num)) => {
+      Bson::Decimal128(parse_decimal(num).ok_or_else(invalid)?)
+    }
+    ("$binary", Value::Object(binary)) => {
+      match (get_str(binary, "base64"), get_str(binary, "subType")) {
+        (Some(base64), Some(subtype)) => to_binary(base64, subtype)?,
+        _ => return Err(invalid()),
+      }
+    }
+    ("$timestamp", Value::Object(timestamp)) => {
+      // both halves are 32-bit unsigned integers
+      let get_u32 = |key: &str| {
+        let num = timestamp.get(key).and_then(Value::as_u64)?;
+        u32::try_from(num).ok()
+      };
+      match (get_u32("t"), get_u32("i")) {
+        (Some(t), Some(i)) => {
+          Bson::TimeStamp(((u64::from(t) << 32) | u64::from(i)) as i64)
+        }
+        _ => return Err(invalid()),
+      }
+    }
+    ("$regularExpression", Value::Object(regex)) => {
+      match (get_str(regex, "pattern"), get_str(regex, "options")) {
+        (Some(pattern), Some(options)) => {
+          Bson::RegExp(pattern.to_string(), options.to_string())
+        }
+        _ => return Err(invalid()),
+      }
+    }
+    ("$date", date) => Bson::UtcDatetime(parse_date(date).ok_or_else(invalid)?),
+    ("$minKey", _) | ("$maxKey", _) => {
+      let mut doc = Document::new();
+      doc.insert(key, 1);
+      Bson::Document(doc)
+    }
+    ("$oid", _)
+    | ("$symbol", _)
+    | ("$code", _)
+    | ("$numberInt", _)
+    | ("$numberLong", _)
+    | ("$numberDouble", _)
+    | ("$numberDecimal", _)
+    | ("$binary", _)
+    | ("$timestamp", _)
+    | ("$regularExpression", _) => return Err(invalid()),
+    _ => return Ok(None),
+  };
+  Ok(Some(bson))
+}
+
+// Legacy forms that take two keys, and code with scope.
+fn from_extended_pair(
+  map: &Map<String, Value>,
+) -> Result<Option<Bson>, failure::Error> {
+  if let (Some(pattern), Some(options)) =
+    (get_str(map, "$regex"), get_str(map, "$options"))
+  {
+    return Ok(Some(Bson::RegExp(pattern.to_string(), options.to_string())));
+  }
+  if let (Some(base64), Some(subtype)) =
+    (get_str(map, "$binary"), get_str(map, "$type"))
+  {
+    return Ok(Some(to_binary(base64, subtype)?));
+  }
+  if let (Some(code), Some(Value::Object(scope))) =
+    (get_str(map, "$code"), map.get("$scope"))
+  {
+    let scope = to_document(scope.clone())?;
+    return Ok(Some(Bson::JavaScriptCodeWithScope(code.to_string(), scope)));
+  }
+  Ok(None)
+}
+
+fn get_str<'a>(map: &'a Map<String, Value>, key: &str) -> Option<&'a str> {
+  map.get(key).and_then(Value::as_str)
+}
+
+fn to_binary(base64: &str, subtype: &str) -> Result<Bson, failure::Error> {
+  let subtype = u8::from_str_radix(subtype, 16)?;
+  Ok(Bson::Binary(
+    BinarySubtype::from(subtype),
+    base64::decode(base64)?,
+  ))
+}
+
+// Special values are only spelled `Infinity`, `-Infinity` and `NaN`, while
+// `f64::from_str` also accepts i.e. `inf` or `nan`.
+fn parse_double(num: &str) -> Result<f64, failure::Error> {
+  let num = match num {
+    "Infinity" => f64::INFINITY,
+    "-Infinity" => f64::NEG_INFINITY,
+    "NaN" => f64::NAN,
+    _ => {
+      let parsed: f64 = num.parse()?;
+      if !parsed.is_finite() {
+        return Err(format_err!("Invalid double: {}", num));
+      }
+      parsed
+    }
+  };
+  Ok(num)
+}
+
+// Decimal128 strings are an optionally signed number with an optional
+// fraction and exponent, i.e. `-1.5E+3`, or one of the special values.
+// Anything else is rejected before the bson crate gets to parse it.
+fn parse_decimal(num: &str) -> Option<Decimal128> {
+  let unsigned = num.trim_start_matches(['-', '+']);
+  if num.len() - unsigned.len() > 1 {
+    return None;
+  }
+  let is_special = ["Infinity", "Inf", "NaN"].contains(&unsigned);
+  if !is_special && !is_decimal_number(unsigned) {
+    return None;
+  }
+  Some(Decimal128::from_str(num))
+}
+
+fn is_decimal_number(num: &str) -> bool {
+  let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+  let (mantissa, exponent) = match num.find(['e', 'E']) {
+    Some(i) => (&num[..i], Some(&num[i + 1..])),
+    None => (num, None),
+  };
+  let (int, fraction) = match mantissa.find('.') {
+    Some(i) => (&mantissa[..i], &mantissa[i + 1..]),
+    None => (mantissa, ""),
+  };
+  if int.is_empty() && fraction.is_empty() {
+    return false;
+  }
+  if !is_digits(int) || !is_digits(fraction) {
+    return false;
+  }
+  match exponent {
+    Some(exponent) => {
+      let exponent = exponent.strip_prefix(['-', '+']).unwrap_or(exponent);
+      !exponent.is_empty() && is_digits(exponent)
+    }
+    None => true,
+  }
+}
+
+// Dates are an ISO-8601 string in relaxed form, milliseconds since the epoch
+// as `{ "$numberLong": "..." }` in canonical form, or as a plain number in
+// legacy form.
+fn parse_date(date: &Value) -> Option<DateTime<Utc>> {
+  let millis = match date {
+    Value::String(date) => {
+      let date = DateTime::parse_from_rfc3339(date).ok()?;
+      return Some(date.with_timezone(&Utc));
+    }
+    Value::Number(millis) => millis.as_i64()?,
+    Value::Object(millis) => get_str(millis, "$numberLong")?.parse().ok()?,
+    _ => return None,
+  };
+  let nanos = (millis.rem_euclid(1000) * 1_000_000) as u32;
+  Utc.timestamp_opt(millis.div_euclid(1000), nanos).single()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn convert(value: Value) -> Bson {
+    to_bson(value).unwrap()
+  }
+
+  #[test]
+  fn it_converts_relaxed_numbers() {
+    assert_eq!(convert(json!(42)), Bson::I32(42));
+    assert_eq!(convert(json!(6_451_617_244_i64)), Bson::I64(6_451_617_244));
+    assert_eq!(convert(json!(1.5)), Bson::FloatingPoint(1.5));
+  }
+
+  #[test]
+  fn it_converts_canonical_numbers() {
+    assert_eq!(convert(json!({ "$numberInt": "42" })), Bson::I32(42));
+    assert_eq!(convert(json!({ "$numberLong": "42" })), Bson::I64(42));
+    assert_eq!(
+      convert(json!({ "$numberDouble": "-1.5" })),
+      Bson::FloatingPoint(-1.5)
+    );
+    assert_eq!(
+      convert(json!({ "$numberDouble": "-Infinity" })),
+      Bson::FloatingPoint(f64::NEG_INFINITY)
+    );
+    assert!(to_bson(json!({ "$numberLong": 42 })).is_err());
+    assert!(to_bson(json!({ "$numberInt": "forty-two" })).is_err());
+    for num in &["inf", "infinity", "-INFINITY", "nan", "NAN", "+Infinity"] {
+      assert!(to_bson(json!({ "$numberDouble": num })).is_err(), "{}", num);
+    }
+  }
+
+  #[test]
+  fn it_converts_decimals() {
+    for num in &["1.5", "-1.5E+3", "0.001", "12e-2", "-Infinity", "NaN"] {
+      let decimal = convert(json!({ "$numberDecimal": num }));
+      assert!(matches!(decimal, Bson::Decimal128(_)), "{}", num);
+    }
+    for num in &["", "one", "1.2.3", "1e", ".", "--1", "1.5E+3x"] {
+      let decimal = to_bson(json!({ "$numberDecimal": num }));
+      assert!(decimal.is_err(), "{}", num);
+    }
+    assert!(to_bson(json!({ "$numberDecimal": 1.5 })).is_err());
+  }
+
+  #[test]
+  fn it_converts_dates() {
+    let date: DateTime<Utc> = "2014-01-31T22:26:33Z".parse().unwrap();
+    let millis = date.timestamp_millis();
+    for value in &[
+      json!({ "$date": "2014-01-31T22:26:33.000Z" }),
+      json!({ "$date": { "$numberLong": millis.to_string() } }),
+      json!({ "$date": millis }),
+    ] {
+      assert_eq!(convert(value.clone()), Bson::UtcDatetime(date));
+    }
+    let before_epoch = convert(json!({ "$date": { "$numberLong": "-1" } }));
+    let date: DateTime<Utc> = "1969-12-31T23:59:59.999Z".parse().unwrap();
+    assert_eq!(before_epoch, Bson::UtcDatetime(date));
+    assert!(to_bson(json!({ "$date": "yesterday" })).is_err());
+  }
+
+  #[test]
+  fn it_converts_binary() {
+    let binary = Bson::Binary(BinarySubtype::from(4), vec![1, 2, 3]);
+    let canonical = json!({ "$binary": { "base64": "AQID", "subType": "04" } });
+    assert_eq!(convert(canonical), binary);
+    let legacy = json!({ "$binary": "AQID", "$type": "04" });
+    assert_eq!(convert(legacy), binary);
+  }
+
+  #[test]
+  fn it_converts_timestamps_and_regexes() {
+    assert_eq!(
+      convert(json!({ "$timestamp": { "t": 1, "i": 2 } })),
+      Bson::TimeStamp((1 << 32) | 2)
+    );
+    let too_large = json!({ "$timestamp": { "t": 1, "i": 4_294_967_296_u64 } });
+    assert!(to_bson(too_large).is_err());
+    let negative = json!({ "$timestamp": { "t": -1, "i": 2 } });
+    assert!(to_bson(negative).is_err());
+    let regex = Bson::RegExp("^Nori".to_string(), "i".to_string());
+    let canonical = json!({
+      "$regularExpression": { "pattern": "^Nori", "options": "i" }
+    });
+    assert_eq!(convert(canonical), regex);
+    assert_eq!(
+      convert(json!({ "$regex": "^Nori", "$options": "i" })),
+      regex
+    );
+  }
+
+  #[test]
+  fn it_converts_nested_documents() {
+    let value = json!({
+      "_id": { "$oid": "50319491fe4dce143835c552" },
+      "phone_no": { "$numberLong": "6451617244" },
+      "tags": [{ "$numberInt": "1" }, "cat"],
+      "owner": { "$ref": "owners", "$id": 1 }
+    });
+    let doc = match convert(value) {
+      Bson::Document(doc) => doc,
+      _ => panic!("expected a document"),
+    };
+    assert!(doc.get_object_id("_id").is_ok());
+    assert_eq!(doc.get_i64("phone_no").unwrap(), 6_451_617_244);
+    assert_eq!(doc.get_array("tags").unwrap()[0], Bson::I32(1));
+    let owner = doc.get_document("owner").unwrap();
+    assert_eq!(owner.get_str("$ref").unwrap(), "owners");
+  }
+
+  #[test]
+  fn it_gets_key_type() {
+    let min_key = match convert(json!({ "$minKey": 1 })) {
+      Bson::Document(doc) => doc,
+      _ => panic!("expected a document"),
+    };
+    assert_eq!(get_key_type(&min_key), Some(MIN_KEY));
+    let doc = match convert(json!({ "name": "Nori" })) {
+      Bson::Document(doc) => doc,
+      _ => panic!("expected a document"),
+    };
+    assert_eq!(get_key_type(&doc), None);
+  }
+}
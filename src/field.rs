@@ -1,4 +1,4 @@
-use super::{Bson, FieldType};
+use super::{Bson, FieldType, SchemaOptions, SchemaParser};
 use std::collections::HashMap;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -9,10 +9,20 @@ pub struct Field {
   pub bson_types: Vec<String>,
   pub probability: f32,
   pub types: HashMap<String, FieldType>,
+  #[serde(default, skip_serializing_if = "crate::state::skip")]
+  options: SchemaOptions,
 }
 
 impl Field {
   pub fn new<T, U>(name: T, path: U) -> Self
+  where
+    T: Into<String>,
+    U: Into<String>,
+  {
+    Field::with_options(name, path, SchemaOptions::default())
+  }
+
+  pub fn with_options<T, U>(name: T, path: U, options: SchemaOptions) -> Self
   where
     T: Into<String>,
     U: Into<String>,
@@ -24,11 +34,13 @@ impl Field {
       bson_types: Vec::new(),
       probability: 0.0,
       types: HashMap::new(),
+      options,
     }
   }
 
   pub fn create_type(&mut self, value: &Bson) {
-    let mut field_type = FieldType::new(&self.path, &value);
+    let mut field_type =
+      FieldType::with_options(&self.path, &value, self.options);
     field_type.add_to_type(&value, self.count);
     self.bson_types.push(field_type.bson_type.to_string());
     self
@@ -36,10 +48,76 @@ impl Field {
       .insert(FieldType::get_type(&value), field_type.to_owned());
   }
 
+  pub fn update_type(&mut self, value: &Bson) {
+    self.update_count();
+    if !self.does_field_type_exist(&value) {
+      // field type doesn't exist in field.types, create a new field_type
+      self.create_type(&value);
+    } else {
+      let type_val = FieldType::get_type(&value);
+      let field_type = self.types.get_mut(&type_val);
+      if let Some(field_type) = field_type {
+        field_type.update_type(&value);
+      }
+    }
+  }
+
   pub fn does_field_type_exist(&mut self, value: &Bson) -> bool {
     self.bson_types.contains(&FieldType::get_type(&value))
   }
 
+  pub fn merge(&mut self, mut other: Field) {
+    self.update_count_by(other.count);
+    // go through other's bson_types to keep the order types were seen in
+    for type_val in other.bson_types {
+      let field_type = match other.types.remove(&type_val) {
+        Some(field_type) => field_type,
+        None => continue,
+      };
+      match self.types.get_mut(&type_val) {
+        Some(existing) => existing.merge(field_type),
+        None => {
+          self.bson_types.push(type_val.to_string());
+          self.types.insert(type_val, field_type);
+        }
+      }
+    }
+  }
+
+  /// Nested schemas of this field: the schema of its Document type and the
+  /// schemas of Documents held in its Array type.
+  pub fn get_schemas(&self) -> Vec<&SchemaParser> {
+    let mut schemas = Vec::new();
+    if let Some(field_type) = self.types.get(crate::field_type::DOCUMENT) {
+      schemas.extend(field_type.schema.as_ref());
+    }
+    let items = self
+      .types
+      .get(crate::field_type::ARRAY)
+      .and_then(|field_type| field_type.items.as_ref());
+    if let Some(items) = items {
+      schemas.extend(items.get_schemas());
+    }
+    schemas
+  }
+
+  /// Moves the nested schemas out of this field, leaving its Document type
+  /// and the Documents in its Array type without one.
+  pub fn take_schemas(&mut self) -> Vec<SchemaParser> {
+    let mut schemas = Vec::new();
+    if let Some(field_type) = self.types.get_mut(crate::field_type::DOCUMENT) {
+      schemas.extend(field_type.schema.take());
+    }
+    let items = self
+      .types
+      .get_mut(crate::field_type::ARRAY)
+      .and_then(|field_type| field_type.items.as_mut());
+    if let Some(items) = items {
+      schemas.extend(items.take_schemas());
+    }
+    schemas
+  }
+
   pub fn get_path(name: String, path: Option<String>) -> String {
     match path {
       None => name,
@@ -60,7 +138,8 @@ impl Field {
 
   pub fn update_for_missing(&mut self, missing: usize) {
     // create new field_types of "Null" for missing fields.
-    let mut null_field_type = FieldType::new(&self.path, &Bson::Null);
+    let mut null_field_type =
+      FieldType::with_options(&self.path, &Bson::Null, self.options);
     null_field_type.add_to_type(&Bson::Null, self.count);
     null_field_type.count = missing;
     self.types.insert(
@@ -90,6 +169,7 @@ impl Field {
 #[cfg(test)]
 mod tests {
   use super::*;
+  use bson::{bson, doc};
   // use crate::test::Bencher;
 
   #[test]
@@ -149,6 +229,42 @@ mod tests {
   //   bench.iter(|| field.update_count());
   // }
 
+  #[test]
+  fn it_updates_type() {
+    let mut field = Field::new("age", "age");
+    field.create_type(&Bson::I32(9));
+    field.update_type(&Bson::I32(3));
+    field.update_type(&Bson::String("nine".to_string()));
+    assert_eq!(field.count, 3);
+    assert_eq!(field.bson_types, vec!["Int", "String"]);
+    assert_eq!(field.types["Int"].count, 2);
+    assert_eq!(field.types["String"].count, 1);
+  }
+
+  #[test]
+  fn it_merges() {
+    let mut field = Field::new("age", "age");
+    field.create_type(&Bson::I32(9));
+    let mut other = Field::new("age", "age");
+    other.create_type(&Bson::String("nine".to_string()));
+    other.update_type(&Bson::I32(3));
+    field.merge(other);
+    assert_eq!(field.count, 3);
+    assert_eq!(field.bson_types, vec!["Int", "String"]);
+    assert_eq!(field.types["Int"].count, 2);
+    assert_eq!(field.types["String"].count, 1);
+  }
+
+  #[test]
+  fn it_gets_schemas() {
+    let subdoc = Bson::Document(doc! { "name": "Nori" });
+    let mut field = Field::new("cats", "cats");
+    assert!(field.get_schemas().is_empty());
+    field.create_type(&subdoc);
+    field.update_type(&Bson::Array(vec![subdoc.clone()]));
+    assert_eq!(field.get_schemas().len(), 2);
+  }
+
   #[allow(clippy::float_cmp)]
   #[test]
   fn it_sets_probability() {
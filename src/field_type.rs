@@ -1,5 +1,14 @@
 #![allow(clippy::option_map_unit_fn)]
-use super::{Bson, SchemaParser, ValueType};
+use super::{
+  extended_json, reference, sampling, Bson, DateStats, EnumCounter, Field,
+  GeoStats, HyperLogLog, LengthStats, NumericStats, ObjectIdStats, Percentiles,
+  Reference, Rng, SchemaOptions, SchemaParser, SpaceSaving, StringStats,
+  TDigest, TopValue, ValueType,
+};
+use bson::{oid::ObjectId, Document};
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct FieldType {
@@ -7,12 +16,51 @@ pub struct FieldType {
   pub count: usize,
   pub bson_type: String,
   pub probability: f32,
-  #[serde(skip_serializing_if = "Vec::is_empty")]
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
   pub values: Vec<ValueType>,
   pub has_duplicates: bool,
   #[serde(skip_serializing_if = "Option::is_none")]
   pub schema: Option<SchemaParser>,
+  // Array elements are tracked as a field of their own, so each element type
+  // gets its own count and probability relative to all elements.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub items: Option<Field>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub lengths: Option<LengthStats>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub numeric_stats: Option<NumericStats>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub percentiles: Option<Percentiles>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub string_stats: Option<StringStats>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub date_stats: Option<DateStats>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub object_id_stats: Option<ObjectIdStats>,
+  // Set when a Document field type holds GeoJSON geometries.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub geo: Option<GeoStats>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub reference: Option<Reference>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub top_values: Vec<TopValue>,
+  // Set when a String field type has few distinct values, making it an enum
+  // candidate: each observed value with its count.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub enum_values: Option<BTreeMap<String, usize>>,
   pub unique: Option<usize>,
+  #[serde(default, skip_serializing_if = "crate::state::skip")]
+  options: SchemaOptions,
+  #[serde(default, skip_serializing_if = "crate::state::skip")]
+  rng: Rng,
+  #[serde(default, skip_serializing_if = "crate::state::skip")]
+  distinct: HyperLogLog,
+  #[serde(default, skip_serializing_if = "crate::state::skip")]
+  digest: TDigest,
+  #[serde(default, skip_serializing_if = "crate::state::skip")]
+  frequent: SpaceSaving,
+  #[serde(default, skip_serializing_if = "crate::state::skip")]
+  enum_counter: EnumCounter,
 }
 
 pub static JAVASCRIPT_CODE_WITH_SCOPE: &str = "JavaScriptCodeWithScope";
@@ -32,9 +80,19 @@ pub static ARRAY: &str = "Array";
 pub static I32: &str = "Int";
 pub static I64: &str = "Long";
 pub static NULL: &str = "Null";
+pub static MIN_KEY: &str = "MinKey";
+pub static MAX_KEY: &str = "MaxKey";
 
 impl FieldType {
   pub fn new<S: Into<String>>(path: S, value: &Bson) -> Self {
+    FieldType::with_options(path, value, SchemaOptions::default())
+  }
+
+  pub fn with_options<S: Into<String>>(
+    path: S,
+    value: &Bson,
+    options: SchemaOptions,
+  ) -> Self {
     FieldType {
       path: path.into(),
       bson_type: FieldType::get_type(&value),
@@ -46,7 +104,24 @@ impl FieldType {
       // on finalize method, should also destructure it somehow (everything from
       // this structure should come up one level)
       schema: None,
+      items: None,
+      lengths: None,
+      numeric_stats: None,
+      percentiles: None,
+      string_stats: None,
+      date_stats: None,
+      object_id_stats: None,
+      geo: None,
+      reference: None,
+      top_values: Vec::new(),
+      enum_values: None,
       unique: None,
+      options,
+      rng: Rng::with_seed(options.seed),
+      distinct: HyperLogLog::default(),
+      digest: TDigest::default(),
+      frequent: SpaceSaving::new(options.top_k * 4),
+      enum_counter: EnumCounter::new(options.enum_max_values),
     }
   }
 
@@ -56,22 +131,21 @@ impl FieldType {
 
     match value {
       Bson::Array(arr) => {
-        self
-          .values
-          .extend(arr.iter().filter_map(|val| Self::get_value(val)));
+        self.update_lengths(arr.len());
+        self.update_items(arr);
       }
-      Bson::Document(subdoc) => {
-        let mut schema_parser = SchemaParser::new();
+      // MinKey and MaxKey are documents as well, but have no schema
+      Bson::Document(subdoc) if self.bson_type == DOCUMENT => {
+        let mut schema_parser = SchemaParser::with_options(self.options);
         schema_parser.generate_field(
           subdoc.to_owned(),
           Some(self.path.clone()),
           Some(self.count),
         );
         self.set_schema(schema_parser);
+        self.update_subdoc(subdoc);
       }
-      _ => {
-        Self::get_value(&bson_value).map(|v| self.values.push(v));
-      }
+      _ => self.observe_value(&bson_value),
     }
   }
 
@@ -94,6 +168,82 @@ impl FieldType {
     self.update_value(&value);
   }
 
+  pub fn merge(&mut self, other: FieldType) {
+    match self.options.max_values {
+      Some(max) => {
+        let values = std::mem::take(&mut self.values);
+        self.values = sampling::merge_samples(
+          values,
+          self.count,
+          other.values,
+          other.count,
+          max,
+          &mut self.rng,
+        );
+      }
+      None => self.values.extend(other.values),
+    }
+    self.count += other.count;
+    self.distinct.merge(&other.distinct);
+    self.digest.merge(&other.digest);
+    self.frequent.merge(&other.frequent);
+    self.enum_counter.merge(&other.enum_counter);
+    if let Some(other_schema) = other.schema {
+      match &mut self.schema {
+        Some(schema) => schema.merge(other_schema),
+        None => self.set_schema(other_schema),
+      }
+    }
+    if let Some(other_items) = other.items {
+      match &mut self.items {
+        Some(items) => items.merge(other_items),
+        None => self.items = Some(other_items),
+      }
+    }
+    if let Some(other_lengths) = other.lengths {
+      match &mut self.lengths {
+        Some(lengths) => lengths.merge(&other_lengths),
+        None => self.lengths = Some(other_lengths),
+      }
+    }
+    if let Some(other_stats) = other.numeric_stats {
+      match &mut self.numeric_stats {
+        Some(stats) => stats.merge(&other_stats),
+        None => self.numeric_stats = Some(other_stats),
+      }
+    }
+    if let Some(other_stats) = other.string_stats {
+      match &mut self.string_stats {
+        Some(stats) => stats.merge(&other_stats),
+        None => self.string_stats = Some(other_stats),
+      }
+    }
+    if let Some(other_stats) = other.date_stats {
+      match &mut self.date_stats {
+        Some(stats) => stats.merge(&other_stats),
+        None => self.date_stats = Some(other_stats),
+      }
+    }
+    if let Some(other_stats) = other.object_id_stats {
+      match &mut self.object_id_stats {
+        Some(stats) => stats.merge(&other_stats),
+        None => self.object_id_stats = Some(other_stats),
+      }
+    }
+    if let Some(other_geo) = other.geo {
+      match &mut self.geo {
+        Some(geo) => geo.merge(&other_geo),
+        None => self.geo = Some(other_geo),
+      }
+    }
+    if let Some(other_reference) = other.reference {
+      match &mut self.reference {
+        Some(reference) => reference.merge(&other_reference),
+        None => self.reference = Some(other_reference),
+      }
+    }
+  }
+
   pub fn get_value(value: &Bson) -> Option<ValueType> {
     match value {
       Bson::RegExp(val, _)
@@ -102,15 +252,16 @@ impl FieldType {
       | Bson::Symbol(val) => Some(ValueType::Str(val.to_string())),
       Bson::I64(num) | Bson::TimeStamp(num) => Some(ValueType::I64(*num)),
       Bson::FloatingPoint(num) => Some(ValueType::FloatingPoint(*num)),
-      Bson::UtcDatetime(date) => Some(ValueType::Str(date.clone().to_string())),
+      Bson::UtcDatetime(date) => Some(ValueType::UtcDatetime(*date)),
       Bson::Decimal128(d128) => Some(ValueType::Decimal128(d128.to_string())),
       Bson::Boolean(boolean) => Some(ValueType::Boolean(*boolean)),
       Bson::String(string) => Some(ValueType::Str(string.to_string())),
       Bson::Binary(_, vec) => Some(ValueType::Binary(vec.clone())),
-      Bson::ObjectId(id) => Some(ValueType::Str(id.to_string())),
+      Bson::ObjectId(id) => Some(ValueType::ObjectId(id.to_string())),
       Bson::I32(num) => Some(ValueType::I32(*num)),
       Bson::Null => Some(ValueType::Null("Null".to_string())),
-      // Array and Document get handeled separately
+      // Array and Document get handeled separately: arrays keep their elements
+      // in `items`, documents (including array elements) in `schema`.
       _ => None,
     }
   }
@@ -119,6 +270,25 @@ impl FieldType {
     self.set_probability(parent_count);
     self.set_unique();
     self.set_duplicates();
+    self.percentiles = Percentiles::from_digest(&mut self.digest);
+    if let Some(string_stats) = &mut self.string_stats {
+      string_stats.finalise();
+    }
+    // values of Null types created for missing fields aren't counted
+    if self.bson_type != NULL {
+      self.top_values = self.frequent.top(self.options.top_k);
+    }
+    self.enum_values = self
+      .enum_counter
+      .get_enum(self.count, self.options.enum_max_ratio);
+    if let Some(schema) = &mut self.schema {
+      schema.finalise_schema();
+    }
+    if let Some(items) = &mut self.items {
+      // item probabilities are relative to the total number of elements seen
+      let items_count = items.count;
+      items.finalise_field(items_count);
+    }
   }
 
   pub fn get_type(value: &Bson) -> String {
@@ -133,7 +303,11 @@ impl FieldType {
       Bson::TimeStamp(_) => TIMESTAMP.to_string(),
       Bson::Binary(_, _) => BINARY.to_string(),
       Bson::RegExp(_, _) => REGEXP.to_string(),
-      Bson::Document(_) => DOCUMENT.to_string(),
+      Bson::Document(doc) => match extended_json::get_key_type(doc) {
+        Some(key) if key == extended_json::MIN_KEY => MIN_KEY.to_string(),
+        Some(_) => MAX_KEY.to_string(),
+        None => DOCUMENT.to_string(),
+      },
       Bson::ObjectId(_) => OBJECTID.to_string(),
       Bson::Boolean(_) => BOOLEAN.to_string(),
       Bson::Symbol(_) => SYMBOL.to_string(),
@@ -147,14 +321,20 @@ impl FieldType {
 
   fn get_duplicates(&mut self) -> bool {
     let unique = self.get_unique();
+    if !self.is_exact() {
+      return unique < self.distinct.len();
+    }
     let total_values = self.values.len();
     (total_values - unique) != 0
   }
 
   fn get_unique(&mut self) -> usize {
+    if !self.is_exact() {
+      return self.distinct.estimate();
+    }
     let mut vec = self.values.clone();
-    vec.sort_by(|a, b| a.partial_cmp(b).unwrap());
-    vec.dedup();
+    vec.sort_by(ValueType::total_cmp);
+    vec.dedup_by(|a, b| a.total_cmp(b) == Ordering::Equal);
     vec.len()
   }
 
@@ -163,6 +343,15 @@ impl FieldType {
     self.has_duplicates = duplicates
   }
 
+  // Unique values can only be counted exactly while every value is kept.
+  fn is_exact(&self) -> bool {
+    let sampled = self
+      .options
+      .max_values
+      .map_or(false, |max| self.distinct.len() > max);
+    self.options.exact_unique && !sampled
+  }
+
   fn set_schema(&mut self, schema: SchemaParser) {
     self.schema = Some(schema)
   }
@@ -175,6 +364,159 @@ impl FieldType {
     self.probability = self.count as f32 / parent_count as f32
   }
 
+  fn name(&self) -> &str {
+    self.path.rsplit('.').next().unwrap_or(&self.path)
+  }
+
+  fn update_lengths(&mut self, len: usize) {
+    match &mut self.lengths {
+      Some(lengths) => lengths.update(len),
+      None => self.lengths = Some(LengthStats::new(len)),
+    }
+  }
+
+  fn update_items(&mut self, arr: &[Bson]) {
+    for value in arr {
+      match &mut self.items {
+        Some(items) => items.update_type(value),
+        None => {
+          let mut items =
+            Field::with_options(self.name(), self.path.as_str(), self.options);
+          items.create_type(value);
+          self.items = Some(items);
+        }
+      }
+    }
+  }
+
+  // Keeps a scalar value and updates the statistics collected about it.
+  fn observe_value(&mut self, value: &Bson) {
+    self.update_numeric_stats(value);
+    self.update_quantiles(value);
+    if let Bson::String(string) = value {
+      self.update_string_stats(string);
+      self.enum_counter.insert(string);
+    }
+    if let Bson::UtcDatetime(date) = value {
+      self.update_date_stats(*date);
+    }
+    if let Bson::ObjectId(id) = value {
+      self.update_object_id_stats(id);
+      if Reference::is_reference_name(self.name()) {
+        self.update_reference(reference::MANUAL, None);
+      }
+    }
+    Self::get_value(value).map(|v| self.push_value(v));
+  }
+
+  // Numeric value of Int, Long, Double and Decimal128 values. NaN and
+  // infinities can't be ordered or averaged, so they are left out of numeric
+  // statistics and percentiles.
+  fn get_number(value: &Bson) -> Option<f64> {
+    let num = match value {
+      Bson::I32(num) => f64::from(*num),
+      Bson::I64(num) => *num as f64,
+      Bson::FloatingPoint(num) => *num,
+      Bson::Decimal128(d128) => d128.to_string().parse::<f64>().ok()?,
+      _ => return None,
+    };
+    if num.is_finite() {
+      Some(num)
+    } else {
+      None
+    }
+  }
+
+  fn update_numeric_stats(&mut self, value: &Bson) {
+    if let Some(num) = Self::get_number(value) {
+      match &mut self.numeric_stats {
+        Some(stats) => stats.update(num),
+        None => self.numeric_stats = Some(NumericStats::new(num)),
+      }
+    }
+  }
+
+  fn update_string_stats(&mut self, string: &str) {
+    match &mut self.string_stats {
+      Some(stats) => stats.update(string),
+      None => self.string_stats = Some(StringStats::new(string)),
+    }
+  }
+
+  fn update_date_stats(&mut self, date: DateTime<Utc>) {
+    match &mut self.date_stats {
+      Some(stats) => stats.update(date),
+      None => {
+        self.date_stats =
+          Some(DateStats::new(date, self.options.date_histogram))
+      }
+    }
+  }
+
+  // Subdocuments are analysed in a schema of their own; this only looks at
+  // subdocuments with a special meaning.
+  fn update_subdoc(&mut self, doc: &Document) {
+    self.update_geo(doc);
+    if let Some(collection) = Reference::get_dbref(doc) {
+      self.update_reference(reference::DBREF, Some(collection));
+    }
+  }
+
+  fn update_reference(&mut self, kind: &str, collection: Option<String>) {
+    self
+      .reference
+      .get_or_insert_with(|| Reference::new(kind))
+      .update(collection);
+  }
+
+  fn update_geo(&mut self, doc: &Document) {
+    match &mut self.geo {
+      Some(geo) => {
+        geo.update(doc);
+      }
+      None => {
+        let mut geo = GeoStats::new();
+        if geo.update(doc) {
+          self.geo = Some(geo);
+        }
+      }
+    }
+  }
+
+  fn update_object_id_stats(&mut self, id: &ObjectId) {
+    match &mut self.object_id_stats {
+      Some(stats) => stats.update(id),
+      None => self.object_id_stats = Some(ObjectIdStats::new(id)),
+    }
+  }
+
+  fn update_quantiles(&mut self, value: &Bson) {
+    let num = match value {
+      Bson::UtcDatetime(date) => Some(date.timestamp_millis() as f64),
+      _ => Self::get_number(value),
+    };
+    if let Some(num) = num {
+      self.digest.insert(num);
+    }
+  }
+
+  fn push_value(&mut self, value: ValueType) {
+    let hash = value.stable_hash();
+    self.distinct.insert(hash);
+    self.frequent.insert(&value, hash);
+    match self.options.max_values {
+      // count already includes this value
+      Some(max) => sampling::sample(
+        &mut self.values,
+        value,
+        self.count,
+        max,
+        &mut self.rng,
+      ),
+      None => self.values.push(value),
+    }
+  }
+
   fn update_count(&mut self) {
     self.count += 1
   }
@@ -182,13 +524,11 @@ impl FieldType {
   fn update_value(&mut self, value: &Bson) {
     match value {
       Bson::Array(arr) => {
-        self
-          .values
-          .extend(arr.iter().filter_map(|val| Self::get_value(val)));
-      }
-      _ => {
-        Self::get_value(&value).map(|v| self.values.push(v));
+        self.update_lengths(arr.len());
+        self.update_items(arr);
       }
+      Bson::Document(subdoc) => self.update_subdoc(subdoc),
+      _ => self.observe_value(value),
     }
   }
 }
@@ -196,6 +536,8 @@ impl FieldType {
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::DateBucket;
+  use bson::{bson, doc};
   // use crate::test::Bencher;
 
   #[test]
@@ -216,6 +558,365 @@ mod tests {
   #[test]
   fn it_adds_to_type() {}
 
+  #[test]
+  fn it_adds_array_items() {
+    let arr = Bson::Array(vec![
+      Bson::String("cat".to_string()),
+      Bson::I32(1),
+      Bson::Null,
+    ]);
+    let mut field_type = FieldType::new("animals", &arr);
+    field_type.add_to_type(&arr, 1);
+    assert!(field_type.values.is_empty());
+    let items = field_type.items.unwrap();
+    assert_eq!(items.name, "animals");
+    assert_eq!(items.count, 3);
+    assert_eq!(items.bson_types, vec!["String", "Int", "Null"]);
+  }
+
+  #[allow(clippy::float_cmp)]
+  #[test]
+  fn it_tracks_array_lengths() {
+    let arr = Bson::Array(vec![Bson::I32(1), Bson::I32(2)]);
+    let mut field_type = FieldType::new("scores", &arr);
+    field_type.add_to_type(&arr, 1);
+    field_type.update_type(&Bson::Array(vec![]));
+    field_type.update_type(&Bson::Array(vec![Bson::I32(3); 7]));
+    let lengths = field_type.lengths.unwrap();
+    assert_eq!(lengths.min, 0);
+    assert_eq!(lengths.max, 7);
+    assert_eq!(lengths.mean, 3.0);
+    assert_eq!(lengths.histogram, vec![1, 0, 1, 1]);
+  }
+
+  #[allow(clippy::float_cmp)]
+  #[test]
+  fn it_finalises_array_items() {
+    let arr = Bson::Array(vec![
+      Bson::String("cat".to_string()),
+      Bson::String("dog".to_string()),
+      Bson::I32(1),
+      Bson::I32(2),
+    ]);
+    let mut field_type = FieldType::new("animals", &arr);
+    field_type.add_to_type(&arr, 1);
+    field_type.update_type(&Bson::Array(vec![Bson::I32(3), Bson::I32(4)]));
+    field_type.finalise_type(2);
+    let items = field_type.items.unwrap();
+    assert_eq!(items.count, 6);
+    assert_eq!(items.types["String"].count, 2);
+    assert_eq!(items.types["Int"].count, 4);
+    assert_eq!(items.types["Int"].probability, 4.0 / 6.0);
+  }
+
+  #[test]
+  fn it_merges() {
+    let arr = Bson::Array(vec![Bson::I32(1), Bson::I32(2)]);
+    let mut field_type = FieldType::new("scores", &arr);
+    field_type.add_to_type(&arr, 1);
+    let other_arr = Bson::Array(vec![Bson::String("three".to_string())]);
+    let mut other = FieldType::new("scores", &other_arr);
+    other.add_to_type(&other_arr, 1);
+    field_type.merge(other);
+    assert_eq!(field_type.count, 2);
+    let items = field_type.items.unwrap();
+    assert_eq!(items.count, 3);
+    assert_eq!(items.bson_types, vec!["Int", "String"]);
+    let lengths = field_type.lengths.unwrap();
+    assert_eq!(lengths.count, 2);
+    assert_eq!(lengths.min, 1);
+    assert_eq!(lengths.max, 2);
+  }
+
+  #[test]
+  fn it_samples_values() {
+    let options = SchemaOptions {
+      max_values: Some(10),
+      ..SchemaOptions::default()
+    };
+    let mut field_type = FieldType::with_options("age", &Bson::I32(0), options);
+    field_type.add_to_type(&Bson::I32(0), 1);
+    for i in 1..100 {
+      field_type.update_type(&Bson::I32(i));
+    }
+    assert_eq!(field_type.count, 100);
+    assert_eq!(field_type.values.len(), 10);
+  }
+
+  #[test]
+  fn it_merges_sampled_values() {
+    let options = SchemaOptions {
+      max_values: Some(10),
+      ..SchemaOptions::default()
+    };
+    let mut field_type = FieldType::with_options("age", &Bson::I32(0), options);
+    field_type.add_to_type(&Bson::I32(0), 1);
+    let mut other = FieldType::with_options("age", &Bson::I32(0), options);
+    other.add_to_type(&Bson::I32(0), 1);
+    for i in 1..20 {
+      field_type.update_type(&Bson::I32(i));
+      other.update_type(&Bson::I32(i));
+    }
+    field_type.merge(other);
+    assert_eq!(field_type.count, 40);
+    assert_eq!(field_type.values.len(), 10);
+  }
+
+  #[allow(clippy::float_cmp)]
+  #[test]
+  fn it_collects_numeric_stats() {
+    let mut field_type = FieldType::new("age", &Bson::I32(2));
+    field_type.add_to_type(&Bson::I32(2), 1);
+    field_type.update_type(&Bson::I32(4));
+    field_type.update_type(&Bson::I32(9));
+    let stats = field_type.numeric_stats.unwrap();
+    assert_eq!(stats.count, 3);
+    assert_eq!(stats.sum, 15.0);
+    assert_eq!(stats.min, 2.0);
+    assert_eq!(stats.max, 9.0);
+    assert_eq!(stats.mean, 5.0);
+  }
+
+  #[test]
+  fn it_sets_percentiles() {
+    let mut field_type = FieldType::new("age", &Bson::I32(0));
+    field_type.add_to_type(&Bson::I32(0), 1);
+    for i in 1..=100 {
+      field_type.update_type(&Bson::I32(i));
+    }
+    assert!(field_type.percentiles.is_none());
+    field_type.finalise_type(101);
+    let percentiles = field_type.percentiles.unwrap();
+    assert!((percentiles.p50 - 50.0).abs() <= 1.0);
+    assert!(percentiles.p1 < percentiles.p25);
+    assert!(percentiles.p75 < percentiles.p99);
+  }
+
+  #[test]
+  fn it_skips_percentiles_for_strings() {
+    let name = Bson::String("Nori".to_string());
+    let mut field_type = FieldType::new("name", &name);
+    field_type.add_to_type(&name, 1);
+    field_type.finalise_type(1);
+    assert!(field_type.percentiles.is_none());
+  }
+
+  #[test]
+  fn it_sets_top_values() {
+    let options = SchemaOptions {
+      top_k: 2,
+      ..SchemaOptions::default()
+    };
+    let cat = Bson::String("cat".to_string());
+    let mut field_type = FieldType::with_options("animal", &cat, options);
+    field_type.add_to_type(&cat, 1);
+    for animal in &["dog", "cat", "bird", "dog", "cat"] {
+      field_type.update_type(&Bson::String(animal.to_string()));
+    }
+    field_type.finalise_type(6);
+    assert_eq!(field_type.top_values.len(), 2);
+    assert_eq!(
+      field_type.top_values[0].value,
+      ValueType::Str("cat".to_string())
+    );
+    assert_eq!(field_type.top_values[0].count, 3);
+    assert_eq!(
+      field_type.top_values[1].value,
+      ValueType::Str("dog".to_string())
+    );
+    assert_eq!(field_type.top_values[1].count, 2);
+  }
+
+  #[allow(clippy::float_cmp)]
+  #[test]
+  fn it_collects_string_stats() {
+    let street = Bson::String("133 Aloha Ave".to_string());
+    let mut field_type = FieldType::new("street", &street);
+    field_type.add_to_type(&street, 1);
+    field_type.update_type(&Bson::String("50017".to_string()));
+    field_type.update_type(&Bson::String("Oranienstr.".to_string()));
+    let stats = field_type.string_stats.unwrap();
+    assert_eq!(stats.lengths.min, 5);
+    assert_eq!(stats.lengths.max, 13);
+    assert_eq!(stats.lengths.mean, 29.0 / 3.0);
+    assert_eq!(stats.digits_only, 1);
+    assert_eq!(stats.with_whitespace, 1);
+  }
+
+  #[test]
+  fn it_detects_enums() {
+    let options = SchemaOptions {
+      enum_max_values: 3,
+      enum_max_ratio: 0.5,
+      ..SchemaOptions::default()
+    };
+    let active = Bson::String("ACTIVE".to_string());
+    let mut field_type = FieldType::with_options("status", &active, options);
+    field_type.add_to_type(&active, 1);
+    for status in &["PENDING", "ACTIVE", "ACTIVE", "PENDING", "ACTIVE"] {
+      field_type.update_type(&Bson::String(status.to_string()));
+    }
+    field_type.finalise_type(6);
+    let enum_values = field_type.enum_values.unwrap();
+    assert_eq!(enum_values.len(), 2);
+    assert_eq!(enum_values["ACTIVE"], 4);
+    assert_eq!(enum_values["PENDING"], 2);
+  }
+
+  #[test]
+  fn it_skips_enums_with_many_distinct_values() {
+    let options = SchemaOptions {
+      enum_max_values: 3,
+      enum_max_ratio: 0.5,
+      ..SchemaOptions::default()
+    };
+    let name = Bson::String("Nori".to_string());
+    let mut field_type = FieldType::with_options("name", &name, options);
+    field_type.add_to_type(&name, 1);
+    for name in &["Rey", "Chashu", "Nori", "Rey"] {
+      field_type.update_type(&Bson::String(name.to_string()));
+    }
+    field_type.finalise_type(5);
+    // three distinct values, but too many relative to the number of values
+    assert!(field_type.enum_values.is_none());
+  }
+
+  #[test]
+  fn it_collects_date_stats() {
+    let options = SchemaOptions {
+      date_histogram: Some(DateBucket::Year),
+      ..SchemaOptions::default()
+    };
+    let last_login = Bson::UtcDatetime("2014-01-31T22:26:33Z".parse().unwrap());
+    let mut field_type =
+      FieldType::with_options("last_login", &last_login, options);
+    field_type.add_to_type(&last_login, 1);
+    field_type
+      .update_type(&Bson::UtcDatetime("2012-09-14T14:44:21Z".parse().unwrap()));
+    field_type.finalise_type(2);
+    let stats = field_type.date_stats.unwrap();
+    assert_eq!(
+      stats.min,
+      "2012-09-14T14:44:21Z".parse::<DateTime<Utc>>().unwrap()
+    );
+    assert_eq!(
+      stats.max,
+      "2014-01-31T22:26:33Z".parse::<DateTime<Utc>>().unwrap()
+    );
+    assert_eq!(stats.histogram.unwrap().len(), 2);
+    assert!(field_type.percentiles.is_some());
+  }
+
+  #[test]
+  fn it_collects_object_id_stats() {
+    let id = |id| Bson::ObjectId(ObjectId::with_string(id).unwrap());
+    let first = id("50319491fe4dce143835c552");
+    let mut field_type = FieldType::new("_id", &first);
+    field_type.add_to_type(&first, 1);
+    field_type.update_type(&id("5031bb65fe4dce143635c960"));
+    let stats = field_type.object_id_stats.unwrap();
+    assert_eq!(stats.min_timestamp.timestamp(), 0x5031_9491);
+    assert_eq!(stats.max_timestamp.timestamp(), 0x5031_bb65);
+    assert!(stats.monotonic);
+  }
+
+  #[test]
+  fn it_detects_geo_json() {
+    let location = Bson::Document(doc! {
+      "type": "Point",
+      "coordinates": [-106.39, 31.79]
+    });
+    let mut field_type = FieldType::new("location", &location);
+    field_type.add_to_type(&location, 1);
+    field_type.update_type(&Bson::Document(doc! {
+      "type": "Point",
+      "coordinates": [-96.8, 32.76]
+    }));
+    let geo = field_type.geo.unwrap();
+    assert_eq!(geo.count, 2);
+    assert_eq!(geo.types["Point"], 2);
+    assert!(field_type
+      .schema
+      .unwrap()
+      .fields
+      .contains_key("coordinates"));
+
+    let address = Bson::Document(doc! { "city": "Dallas, Texas" });
+    let mut field_type = FieldType::new("address", &address);
+    field_type.add_to_type(&address, 1);
+    assert!(field_type.geo.is_none());
+  }
+
+  #[test]
+  fn it_detects_dbrefs() {
+    let id = || ObjectId::with_string("50319491fe4dce143835c552").unwrap();
+    let member = Bson::Document(doc! { "$ref": "members", "$id": id() });
+    let mut field_type = FieldType::new("member", &member);
+    field_type.add_to_type(&member, 1);
+    field_type.update_type(&Bson::Document(doc! {
+      "$ref": "members",
+      "$id": id(),
+      "$db": "fanclub"
+    }));
+    let reference = field_type.reference.unwrap();
+    assert_eq!(reference.kind, "DBRef");
+    assert_eq!(reference.count, 2);
+    assert_eq!(reference.collections["members"], 1);
+    assert_eq!(reference.collections["fanclub.members"], 1);
+  }
+
+  #[test]
+  fn it_detects_manual_references() {
+    let id = Bson::ObjectId(
+      ObjectId::with_string("50319491fe4dce143835c552").unwrap(),
+    );
+    let mut field_type = FieldType::new("order.member_id", &id);
+    field_type.add_to_type(&id, 1);
+    let reference = field_type.reference.unwrap();
+    assert_eq!(reference.kind, "Manual");
+    assert_eq!(reference.count, 1);
+    assert!(reference.collections.is_empty());
+
+    let mut field_type = FieldType::new("_id", &id);
+    field_type.add_to_type(&id, 1);
+    assert!(field_type.reference.is_none());
+  }
+
+  #[test]
+  fn it_skips_numeric_stats_for_non_numbers() {
+    let name = Bson::String("Nori".to_string());
+    let mut field_type = FieldType::new("name", &name);
+    field_type.add_to_type(&name, 1);
+    assert!(field_type.numeric_stats.is_none());
+    let nan = Bson::FloatingPoint(f64::NAN);
+    let mut field_type = FieldType::new("weight", &nan);
+    field_type.add_to_type(&nan, 1);
+    assert!(field_type.numeric_stats.is_none());
+  }
+
+  #[test]
+  fn it_skips_infinity_in_numeric_stats() {
+    let weight = Bson::FloatingPoint(0.0);
+    let mut field_type = FieldType::new("weight", &weight);
+    field_type.add_to_type(&weight, 1);
+    for i in 1..2000 {
+      let num = if i % 100 == 0 {
+        f64::NEG_INFINITY
+      } else {
+        f64::from(i)
+      };
+      field_type.update_type(&Bson::FloatingPoint(num));
+    }
+    field_type.update_type(&Bson::FloatingPoint(f64::INFINITY));
+    field_type.finalise_type(2001);
+    let stats = field_type.numeric_stats.unwrap();
+    assert_eq!(stats.count, 1981);
+    assert!(stats.variance.is_finite());
+    let percentiles = field_type.percentiles.unwrap();
+    assert!(percentiles.p1.is_finite());
+    assert!(percentiles.p99.is_finite());
+  }
+
   #[test]
   fn it_gets_value_i32() {
     let bson_value = Bson::I32(1234);
@@ -251,6 +952,13 @@ mod tests {
     assert_eq!(value, Some(ValueType::Str("cats".to_string())));
   }
 
+  #[test]
+  fn it_gets_value_utc_datetime() {
+    let date: DateTime<Utc> = "2014-01-31T22:26:33Z".parse().unwrap();
+    let value = FieldType::get_value(&Bson::UtcDatetime(date));
+    assert_eq!(value, Some(ValueType::UtcDatetime(date)));
+  }
+
   // #[bench]
   // fn bench_it_gets_value(bench: &mut Bencher) {
   //   let bson_value = Bson::String("cats".to_string());
@@ -260,6 +968,17 @@ mod tests {
   #[test]
   fn it_gets_type() {}
 
+  #[test]
+  fn it_gets_min_and_max_key_type() {
+    let min_key = Bson::Document(doc! { "$minKey": 1 });
+    assert_eq!(FieldType::get_type(&min_key), "MinKey");
+    let max_key = Bson::Document(doc! { "$maxKey": 1 });
+    let mut field_type = FieldType::new("bound", &max_key);
+    field_type.add_to_type(&max_key, 1);
+    assert_eq!(field_type.bson_type, "MaxKey");
+    assert!(field_type.schema.is_none());
+  }
+
   #[allow(clippy::float_cmp)]
   #[test]
   fn it_sets_probability() {
@@ -269,10 +988,19 @@ mod tests {
     assert_eq!(field_type.probability, 0.1);
   }
 
+  // Counts unique values exactly, so that values can be pushed directly.
+  fn exact_address() -> FieldType {
+    let options = SchemaOptions {
+      exact_unique: true,
+      ..SchemaOptions::default()
+    };
+    let address = Bson::String("Oranienstr. 123".to_string());
+    FieldType::with_options("address", &address, options)
+  }
+
   #[test]
   fn it_gets_unique() {
-    let mut field_type =
-      FieldType::new("address", &Bson::String("Oranienstr. 123".to_string()));
+    let mut field_type = exact_address();
     field_type.values.push(ValueType::Str("Berlin".to_string()));
     field_type
       .values
@@ -294,8 +1022,7 @@ mod tests {
 
   #[test]
   fn it_sets_unique() {
-    let mut field_type =
-      FieldType::new("address", &Bson::String("Oranienstr. 123".to_string()));
+    let mut field_type = exact_address();
     field_type.values.push(ValueType::Str("Berlin".to_string()));
     field_type
       .values
@@ -304,6 +1031,39 @@ mod tests {
     assert_eq!(field_type.unique, Some(2));
   }
 
+  #[test]
+  fn it_estimates_unique() {
+    let city = Bson::String("Berlin".to_string());
+    let mut field_type = FieldType::new("city", &city);
+    field_type.add_to_type(&city, 1);
+    field_type.update_type(&Bson::String("Hamburg".to_string()));
+    field_type.update_type(&city);
+    field_type.finalise_type(3);
+    assert_eq!(field_type.unique, Some(2));
+    assert!(field_type.has_duplicates);
+    // the estimate doesn't need the kept values
+    field_type.values.clear();
+    field_type.finalise_type(3);
+    assert_eq!(field_type.unique, Some(2));
+  }
+
+  #[test]
+  fn it_estimates_unique_once_sampled() {
+    let options = SchemaOptions {
+      max_values: Some(10),
+      ..SchemaOptions::default()
+    };
+    let mut field_type = FieldType::with_options("age", &Bson::I32(0), options);
+    field_type.add_to_type(&Bson::I32(0), 1);
+    for i in 1..100 {
+      field_type.update_type(&Bson::I32(i % 50));
+    }
+    field_type.finalise_type(100);
+    let unique = field_type.unique.unwrap();
+    assert!((45..=55).contains(&unique));
+    assert!(field_type.has_duplicates);
+  }
+
   // #[bench]
   // fn bench_it_sets_unique(bench: &mut Bencher) {
   //   let mut field_type =
@@ -317,8 +1077,7 @@ mod tests {
 
   #[test]
   fn it_gets_duplicates_when_none() {
-    let mut field_type =
-      FieldType::new("address", &Bson::String("Oranienstr. 123".to_string()));
+    let mut field_type = exact_address();
     field_type.values.push(ValueType::Str("Berlin".to_string()));
     field_type
       .values
@@ -329,8 +1088,7 @@ mod tests {
 
   #[test]
   fn it_gets_duplicates_when_some() {
-    let mut field_type =
-      FieldType::new("address", &Bson::String("Oranienstr. 123".to_string()));
+    let mut field_type = exact_address();
     field_type.values.push(ValueType::Str("Berlin".to_string()));
     field_type.values.push(ValueType::Str("Berlin".to_string()));
     let has_duplicates = field_type.get_duplicates();
@@ -350,8 +1108,7 @@ mod tests {
 
   #[test]
   fn it_sets_duplicates() {
-    let mut field_type =
-      FieldType::new("address", &Bson::String("Oranienstr. 123".to_string()));
+    let mut field_type = exact_address();
     field_type.values.push(ValueType::Str("Berlin".to_string()));
     field_type.values.push(ValueType::Str("Berlin".to_string()));
     field_type.set_duplicates();
@@ -0,0 +1,197 @@
+use bson::{Bson, Document};
+use std::collections::BTreeMap;
+
+static GEOMETRY_TYPES: [&str; 6] = [
+  "Point",
+  "MultiPoint",
+  "LineString",
+  "MultiLineString",
+  "Polygon",
+  "MultiPolygon",
+];
+static GEOMETRY_COLLECTION: &str = "GeometryCollection";
+
+/// GeoJSON geometries seen for a Document field type: how many documents of
+/// each geometry type, and the bounding box of all their coordinates as
+/// `[min longitude, min latitude, max longitude, max latitude]`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GeoStats {
+  pub count: usize,
+  pub types: BTreeMap<String, usize>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub bbox: Option<[f64; 4]>,
+}
+
+impl GeoStats {
+  pub fn new() -> Self {
+    GeoStats {
+      count: 0,
+      types: BTreeMap::new(),
+      bbox: None,
+    }
+  }
+
+  /// Updates the stats if `doc` is a GeoJSON geometry and returns whether it
+  /// was one.
+  pub fn update(&mut self, doc: &Document) -> bool {
+    let geo_type = match Self::get_geo_type(doc) {
+      Some(geo_type) => geo_type,
+      None => return false,
+    };
+    self.count += 1;
+    *self.types.entry(geo_type.to_string()).or_insert(0) += 1;
+    self.update_bbox(doc);
+    true
+  }
+
+  pub fn merge(&mut self, other: &GeoStats) {
+    self.count += other.count;
+    for (geo_type, count) in &other.types {
+      *self.types.entry(geo_type.to_string()).or_insert(0) += count;
+    }
+    if let Some([min_lng, min_lat, max_lng, max_lat]) = other.bbox {
+      self.extend_bbox(min_lng, min_lat);
+      self.extend_bbox(max_lng, max_lat);
+    }
+  }
+
+  // Geometry type of a GeoJSON geometry object, i.e. `{ "type": "Point",
+  // "coordinates": [-106.39, 31.79] }`.
+  pub fn get_geo_type(doc: &Document) -> Option<&str> {
+    let geo_type = match doc.get("type") {
+      Some(Bson::String(geo_type)) => geo_type.as_str(),
+      _ => return None,
+    };
+    let member = if geo_type == GEOMETRY_COLLECTION {
+      "geometries"
+    } else if GEOMETRY_TYPES.contains(&geo_type) {
+      "coordinates"
+    } else {
+      return None;
+    };
+    match doc.get(member) {
+      Some(Bson::Array(_)) => Some(geo_type),
+      _ => None,
+    }
+  }
+
+  fn update_bbox(&mut self, doc: &Document) {
+    if let Some(Bson::Array(coordinates)) = doc.get("coordinates") {
+      self.update_positions(coordinates);
+    }
+    if let Some(Bson::Array(geometries)) = doc.get("geometries") {
+      for geometry in geometries {
+        if let Bson::Document(geometry) = geometry {
+          self.update_bbox(geometry);
+        }
+      }
+    }
+  }
+
+  // Coordinates nest arrays of positions up to three levels deep, depending
+  // on the geometry type; a position is an array of numbers.
+  fn update_positions(&mut self, coordinates: &[Bson]) {
+    match (
+      Self::get_number(coordinates.first()),
+      Self::get_number(coordinates.get(1)),
+    ) {
+      (Some(lng), Some(lat)) => self.extend_bbox(lng, lat),
+      _ => {
+        for coordinate in coordinates {
+          if let Bson::Array(positions) = coordinate {
+            self.update_positions(positions);
+          }
+        }
+      }
+    }
+  }
+
+  fn extend_bbox(&mut self, lng: f64, lat: f64) {
+    self.bbox = Some(match self.bbox {
+      Some([min_lng, min_lat, max_lng, max_lat]) => [
+        min_lng.min(lng),
+        min_lat.min(lat),
+        max_lng.max(lng),
+        max_lat.max(lat),
+      ],
+      None => [lng, lat, lng, lat],
+    });
+  }
+
+  fn get_number(value: Option<&Bson>) -> Option<f64> {
+    match value? {
+      Bson::FloatingPoint(num) => Some(*num),
+      Bson::I32(num) => Some(f64::from(*num)),
+      Bson::I64(num) => Some(*num as f64),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use bson::{bson, doc};
+
+  #[test]
+  fn it_gets_geo_type() {
+    let point = doc! { "type": "Point", "coordinates": [-106.39, 31.79] };
+    assert_eq!(GeoStats::get_geo_type(&point), Some("Point"));
+    let collection = doc! { "type": "GeometryCollection", "geometries": [] };
+    assert_eq!(
+      GeoStats::get_geo_type(&collection),
+      Some("GeometryCollection")
+    );
+    let pet = doc! { "type": "Cat", "coordinates": [1, 2] };
+    assert_eq!(GeoStats::get_geo_type(&pet), None);
+    let point = doc! { "type": "Point" };
+    assert_eq!(GeoStats::get_geo_type(&point), None);
+  }
+
+  #[allow(clippy::float_cmp)]
+  #[test]
+  fn it_updates() {
+    let mut stats = GeoStats::new();
+    let point = doc! { "type": "Point", "coordinates": [-106.39, 31.79] };
+    assert!(stats.update(&point));
+    let polygon = doc! {
+      "type": "Polygon",
+      "coordinates": [[[-96.8, 32.76], [-71, 42.3], [-96.8, 45.5]]]
+    };
+    assert!(stats.update(&polygon));
+    assert!(!stats.update(&doc! { "city": "El Paso, Texas" }));
+    assert_eq!(stats.count, 2);
+    assert_eq!(stats.types["Point"], 1);
+    assert_eq!(stats.types["Polygon"], 1);
+    assert_eq!(stats.bbox, Some([-106.39, 31.79, -71.0, 45.5]));
+  }
+
+  #[allow(clippy::float_cmp)]
+  #[test]
+  fn it_updates_geometry_collections() {
+    let mut stats = GeoStats::new();
+    let collection = doc! {
+      "type": "GeometryCollection",
+      "geometries": [
+        { "type": "Point", "coordinates": [10, 20] },
+        { "type": "LineString", "coordinates": [[-5, 0], [3, 40]] }
+      ]
+    };
+    assert!(stats.update(&collection));
+    assert_eq!(stats.types["GeometryCollection"], 1);
+    assert_eq!(stats.bbox, Some([-5.0, 0.0, 10.0, 40.0]));
+  }
+
+  #[allow(clippy::float_cmp)]
+  #[test]
+  fn it_merges() {
+    let mut stats = GeoStats::new();
+    stats.update(&doc! { "type": "Point", "coordinates": [1.0, 2.0] });
+    let mut other = GeoStats::new();
+    other.update(&doc! { "type": "Point", "coordinates": [-1.0, 5.0] });
+    stats.merge(&other);
+    assert_eq!(stats.count, 2);
+    assert_eq!(stats.types["Point"], 2);
+    assert_eq!(stats.bbox, Some([-1.0, 2.0, 1.0, 5.0]));
+  }
+}
@@ -0,0 +1,134 @@
+// Number of bits of a hash used to pick its register. 2^10 one byte registers
+// give a standard error of about 3%.
+const PRECISION: u32 = 10;
+const REGISTERS: usize = 1 << PRECISION;
+
+/// A HyperLogLog sketch, estimating the number of distinct values offered to
+/// it in a fixed amount of memory. Values are offered as 64-bit hashes.
+///
+/// Registers are only allocated once the first value is inserted.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct HyperLogLog {
+  registers: Vec<u8>,
+  len: usize,
+}
+
+impl HyperLogLog {
+  pub fn insert(&mut self, hash: u64) {
+    if self.registers.is_empty() {
+      self.registers = vec![0; REGISTERS];
+    }
+    self.len += 1;
+
+    let index = (hash >> (64 - PRECISION)) as usize;
+    // position of the first set bit in the remaining bits; the extra set bit
+    // caps it for hashes whose remaining bits are all zero.
+    let rank = ((hash << PRECISION) | (1 << (PRECISION - 1))).leading_zeros();
+    let rank = rank as u8 + 1;
+    if rank > self.registers[index] {
+      self.registers[index] = rank;
+    }
+  }
+
+  pub fn merge(&mut self, other: &HyperLogLog) {
+    self.len += other.len;
+    if other.registers.is_empty() {
+      return;
+    }
+    if self.registers.is_empty() {
+      self.registers = other.registers.clone();
+      return;
+    }
+    for (register, other) in self.registers.iter_mut().zip(&other.registers) {
+      if *other > *register {
+        *register = *other;
+      }
+    }
+  }
+
+  /// Estimated number of distinct values inserted.
+  pub fn estimate(&self) -> usize {
+    if self.registers.is_empty() {
+      return 0;
+    }
+    let m = REGISTERS as f64;
+    let alpha = 0.7213 / (1.0 + 1.079 / m);
+    let sum: f64 = self
+      .registers
+      .iter()
+      .map(|register| 2f64.powi(-i32::from(*register)))
+      .sum();
+    let estimate = alpha * m * m / sum;
+
+    let zeros = self.registers.iter().filter(|r| **r == 0).count();
+    if estimate <= 2.5 * m && zeros > 0 {
+      // linear counting is more accurate for small cardinalities
+      (m * (m / zeros as f64).ln()).round() as usize
+    } else {
+      estimate.round() as usize
+    }
+  }
+
+  /// Number of values inserted, including duplicates.
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // splitmix64, to get well distributed hashes for tests
+  fn hash(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+  }
+
+  #[test]
+  fn it_estimates_empty() {
+    let sketch = HyperLogLog::default();
+    assert_eq!(sketch.estimate(), 0);
+    assert!(sketch.is_empty());
+  }
+
+  #[test]
+  fn it_estimates_small_cardinality() {
+    let mut sketch = HyperLogLog::default();
+    for value in &[1, 2, 3, 1, 2, 3] {
+      sketch.insert(hash(*value));
+    }
+    assert_eq!(sketch.estimate(), 3);
+    assert_eq!(sketch.len(), 6);
+  }
+
+  #[test]
+  fn it_estimates_large_cardinality() {
+    let mut sketch = HyperLogLog::default();
+    for value in 0..100_000 {
+      sketch.insert(hash(value % 50_000));
+    }
+    let estimate = sketch.estimate() as f64;
+    assert!((estimate - 50_000.0).abs() / 50_000.0 < 0.1);
+  }
+
+  #[test]
+  fn it_merges() {
+    let mut sketch = HyperLogLog::default();
+    let mut other = HyperLogLog::default();
+    for value in 0..10_000 {
+      sketch.insert(hash(value));
+      other.insert(hash(value + 5_000));
+    }
+    sketch.merge(&other);
+    assert_eq!(sketch.len(), 20_000);
+    let estimate = sketch.estimate() as f64;
+    assert!((estimate - 15_000.0).abs() / 15_000.0 < 0.1);
+  }
+}
@@ -0,0 +1,227 @@
+use super::{extended_json, Bson, SchemaParser};
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde_json::Value;
+use std::fmt;
+use std::fmt::Display;
+use std::io::{BufRead, Read};
+use std::str;
+
+/// A line of line-delimited json that couldn't be written, see
+/// `SchemaParser::write_ndjson_reader()`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LineError {
+  /// Line number, starting at 1.
+  pub line: usize,
+  pub message: String,
+}
+
+impl Display for LineError {
+  fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    write!(formatter, "line {}: {}", self.line, self.message)
+  }
+}
+
+// Writes the documents of a json array as they are deserialized, so that
+// only one document is held in memory at a time.
+struct DocumentsVisitor<'a> {
+  schema_parser: &'a mut SchemaParser,
+}
+
+impl<'de> Visitor<'de> for DocumentsVisitor<'_> {
+  type Value = ();
+
+  fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    formatter.write_str("an array of documents")
+  }
+
+  fn visit_seq<A>(self, mut seq: A) -> Result<(), A::Error>
+  where
+    A: SeqAccess<'de>,
+  {
+    while let Some(value) = seq.next_element::<Value>()? {
+      match extended_json::to_bson(value).map_err(de::Error::custom)? {
+        Bson::Document(doc) => self.schema_parser.write_document(doc),
+        _ => return Err(de::Error::custom("Failed to parse bson")),
+      }
+    }
+    Ok(())
+  }
+}
+
+impl SchemaParser {
+  /// Writes every document of a json array, as written by
+  /// `mongoexport --jsonArray`, to SchemaParser's fields vector. The array is
+  /// streamed rather than loaded into memory at once. Documents before an
+  /// invalid one are still written.
+  ///
+  /// # Arguments
+  /// * `reader` - Anything implementing `io::Read`, i.e. a `BufReader` of a
+  /// file, or a byte slice.
+  ///
+  /// # Examples
+  /// ```
+  /// use mongodb_schema_parser::SchemaParser;
+  ///
+  /// let mut schema_parser = SchemaParser::new();
+  /// let json = r#"[{ "name": "Chashu", "type": "Cat" }, { "name": "Rey" }]"#;
+  /// schema_parser.write_json_array(json.as_bytes()).unwrap();
+  /// let schema = schema_parser.flush();
+  /// ```
+  pub fn write_json_array<R: Read>(
+    &mut self,
+    reader: R,
+  ) -> Result<(), failure::Error> {
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    deserializer.deserialize_seq(DocumentsVisitor {
+      schema_parser: self,
+    })?;
+    deserializer.end()?;
+    Ok(())
+  }
+
+  /// Writes line-delimited json, i.e. the output of `mongoexport`, to
+  /// SchemaParser's fields vector one line at a time. Blank lines are
+  /// skipped. A line that can't be written doesn't stop the remaining lines
+  /// from being written; instead, it is passed to `on_error` as a LineError,
+  /// so that errors of a large input don't need to be kept in memory. Only
+  /// failing to read from `reader` returns an error, after the lines before
+  /// it were written.
+  ///
+  /// # Arguments
+  /// * `reader` - Anything implementing `io::BufRead`, i.e. a `BufReader` of
+  /// a file.
+  /// * `on_error` - Called with every line that can't be written.
+  ///
+  /// # Examples
+  /// ```
+  /// use mongodb_schema_parser::SchemaParser;
+  ///
+  /// let mut schema_parser = SchemaParser::new();
+  /// let ndjson = "{ \"name\": \"Chashu\" }\n\n{ \"name\": \n{ \"name\": \"Rey\" }\n";
+  /// let mut errors = Vec::new();
+  /// schema_parser
+  ///   .write_ndjson_reader(ndjson.as_bytes(), |error| errors.push(error))
+  ///   .unwrap();
+  /// assert_eq!(errors[0].line, 3);
+  /// ```
+  pub fn write_ndjson_reader<R, F>(
+    &mut self,
+    mut reader: R,
+    mut on_error: F,
+  ) -> Result<(), failure::Error>
+  where
+    R: BufRead,
+    F: FnMut(LineError),
+  {
+    let mut buf = Vec::new();
+    let mut line = 0;
+    loop {
+      buf.clear();
+      if reader.read_until(b'\n', &mut buf)? == 0 {
+        return Ok(());
+      }
+      line += 1;
+      // invalid utf-8 only affects its own line, unlike with `lines()`
+      let result = match str::from_utf8(&buf) {
+        Ok(json) if json.trim().is_empty() => Ok(()),
+        Ok(json) => self.write_json(json.trim()),
+        Err(e) => Err(e.into()),
+      };
+      if let Err(e) = result {
+        on_error(LineError {
+          line,
+          message: e.to_string(),
+        });
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io::{self, BufReader};
+
+  #[test]
+  fn it_writes_ndjson_reader() {
+    let mut schema_parser = SchemaParser::new();
+    let ndjson = "{\"name\": \"Nori\"}\r\n\n  \n{\"name\": \"Rey\"}";
+    let mut errors = Vec::new();
+    schema_parser
+      .write_ndjson_reader(ndjson.as_bytes(), |error| errors.push(error))
+      .unwrap();
+    assert!(errors.is_empty());
+    assert_eq!(schema_parser.count, 2);
+  }
+
+  #[test]
+  fn it_reports_ndjson_line_errors() {
+    let mut schema_parser = SchemaParser::new();
+    let mut ndjson = b"{\"name\": \"Nori\"}\n[1, 2]\n{\"name\": \n".to_vec();
+    ndjson.extend_from_slice(b"{\"name\": \"\xff\"}\n{\"name\": \"Rey\"}\n");
+    let mut errors = Vec::new();
+    schema_parser
+      .write_ndjson_reader(ndjson.as_slice(), |error| errors.push(error))
+      .unwrap();
+    let lines: Vec<usize> = errors.iter().map(|error| error.line).collect();
+    assert_eq!(lines, vec![2, 3, 4]);
+    assert_eq!(errors[0].to_string(), "line 2: Failed to parse bson");
+    assert_eq!(schema_parser.count, 2);
+  }
+
+  struct Disconnected;
+
+  impl Read for Disconnected {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+      Err(io::Error::new(io::ErrorKind::Other, "disconnected"))
+    }
+  }
+
+  #[test]
+  fn it_reports_ndjson_line_errors_before_io_errors() {
+    let mut schema_parser = SchemaParser::new();
+    let ndjson = "{\"name\": \"Nori\"}\n[1, 2]\n".as_bytes();
+    let reader = BufReader::new(ndjson.chain(Disconnected));
+    let mut errors = Vec::new();
+    let result =
+      schema_parser.write_ndjson_reader(reader, |error| errors.push(error));
+    assert!(result.is_err());
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].line, 2);
+    assert_eq!(schema_parser.count, 1);
+  }
+
+  #[test]
+  fn it_writes_json_array() {
+    let mut schema_parser = SchemaParser::new();
+    let json = r#"[
+      {"name": "Nori", "age": {"$numberLong": "3"}},
+      {"name": "Rey"}
+    ]"#;
+    schema_parser.write_json_array(json.as_bytes()).unwrap();
+    assert_eq!(schema_parser.count, 2);
+    assert_eq!(schema_parser.fields["name"].count, 2);
+    assert_eq!(schema_parser.fields["age"].bson_types, vec!["Long"]);
+  }
+
+  #[test]
+  fn it_writes_empty_json_array() {
+    let mut schema_parser = SchemaParser::new();
+    schema_parser.write_json_array(" [] ".as_bytes()).unwrap();
+    assert_eq!(schema_parser.count, 0);
+  }
+
+  #[test]
+  fn it_fails_on_invalid_json_array() {
+    let mut schema_parser = SchemaParser::new();
+    let json = r#"{"name": "Nori"}"#;
+    assert!(schema_parser.write_json_array(json.as_bytes()).is_err());
+
+    let json = r#"[{"name": "Nori"}, "Rey", {"name": "Chashu"}]"#;
+    assert!(schema_parser.write_json_array(json.as_bytes()).is_err());
+    assert_eq!(schema_parser.count, 1);
+
+    let json = r#"[{"name": "Nori"}] []"#;
+    assert!(schema_parser.write_json_array(json.as_bytes()).is_err());
+  }
+}
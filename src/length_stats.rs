@@ -0,0 +1,120 @@
+use std::cmp;
+
+/// Running statistics over the lengths of values, e.g. the number of elements
+/// in each array seen for a field type.
+///
+/// `histogram` is bucketed by powers of two: index `0` counts empty values,
+/// index `i` counts lengths in `[2^(i - 1), 2^i)`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LengthStats {
+  pub count: usize,
+  pub total: usize,
+  pub min: usize,
+  pub max: usize,
+  pub mean: f64,
+  pub histogram: Vec<usize>,
+}
+
+impl LengthStats {
+  pub fn new(len: usize) -> Self {
+    let mut stats = LengthStats {
+      count: 0,
+      total: 0,
+      min: len,
+      max: len,
+      mean: 0.0,
+      histogram: Vec::new(),
+    };
+    stats.update(len);
+    stats
+  }
+
+  pub fn update(&mut self, len: usize) {
+    self.count += 1;
+    self.total += len;
+    self.min = cmp::min(self.min, len);
+    self.max = cmp::max(self.max, len);
+    self.mean = self.total as f64 / self.count as f64;
+
+    let bucket = Self::get_bucket(len);
+    if self.histogram.len() <= bucket {
+      self.histogram.resize(bucket + 1, 0);
+    }
+    self.histogram[bucket] += 1;
+  }
+
+  pub fn merge(&mut self, other: &LengthStats) {
+    self.count += other.count;
+    self.total += other.total;
+    self.min = cmp::min(self.min, other.min);
+    self.max = cmp::max(self.max, other.max);
+    self.mean = self.total as f64 / self.count as f64;
+
+    if self.histogram.len() < other.histogram.len() {
+      self.histogram.resize(other.histogram.len(), 0);
+    }
+    for (bucket, count) in other.histogram.iter().enumerate() {
+      self.histogram[bucket] += count;
+    }
+  }
+
+  fn get_bucket(len: usize) -> usize {
+    match len {
+      0 => 0,
+      _ => (64 - (len as u64).leading_zeros()) as usize,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[allow(clippy::float_cmp)]
+  #[test]
+  fn it_creates_new() {
+    let stats = LengthStats::new(3);
+    assert_eq!(stats.count, 1);
+    assert_eq!(stats.min, 3);
+    assert_eq!(stats.max, 3);
+    assert_eq!(stats.mean, 3.0);
+    assert_eq!(stats.histogram, vec![0, 0, 1]);
+  }
+
+  #[allow(clippy::float_cmp)]
+  #[test]
+  fn it_updates() {
+    let mut stats = LengthStats::new(3);
+    stats.update(0);
+    stats.update(9);
+    assert_eq!(stats.count, 3);
+    assert_eq!(stats.total, 12);
+    assert_eq!(stats.min, 0);
+    assert_eq!(stats.max, 9);
+    assert_eq!(stats.mean, 4.0);
+  }
+
+  #[allow(clippy::float_cmp)]
+  #[test]
+  fn it_merges() {
+    let mut stats = LengthStats::new(1);
+    let mut other = LengthStats::new(0);
+    other.update(5);
+    stats.merge(&other);
+    assert_eq!(stats.count, 3);
+    assert_eq!(stats.min, 0);
+    assert_eq!(stats.max, 5);
+    assert_eq!(stats.mean, 2.0);
+    assert_eq!(stats.histogram, vec![1, 1, 0, 1]);
+  }
+
+  #[test]
+  fn it_gets_bucket() {
+    assert_eq!(LengthStats::get_bucket(0), 0);
+    assert_eq!(LengthStats::get_bucket(1), 1);
+    assert_eq!(LengthStats::get_bucket(2), 2);
+    assert_eq!(LengthStats::get_bucket(3), 2);
+    assert_eq!(LengthStats::get_bucket(4), 3);
+    assert_eq!(LengthStats::get_bucket(1000), 10);
+  }
+}
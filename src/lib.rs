@@ -7,15 +7,18 @@
 //! ```rust
 //! extern crate mongodb_schema_parser;
 //! use mongodb_schema_parser::SchemaParser;
-//! use std::fs;
+//! use std::fs::File;
+//! use std::io::BufReader;
 //!
 //! pub fn main () {
-//!   let mut file = fs::read_to_string("examples/fanclub.json").unwrap();
-//!   let file: Vec<&str> = file.trim().split("\n").collect();
+//!   let file = File::open("examples/fanclub.json").unwrap();
 //!   let mut schema_parser = SchemaParser::new();
-//!   for json in file {
-//!     schema_parser.write_json(&json).unwrap();
-//!   }
+//!   // the file is read line by line, and invalid lines are reported
+//!   schema_parser
+//!     .write_ndjson_reader(BufReader::new(file), |error| {
+//!       eprintln!("{}", error);
+//!     })
+//!     .unwrap();
 //!   let result = schema_parser.flush();
 //! }
 //! ```
@@ -62,20 +65,25 @@ extern crate serde_derive;
 extern crate serde;
 use serde_json::Value;
 
-use js_sys::{Object, Uint8Array};
+use js_sys::Object;
 use wasm_bindgen::prelude::*;
 // add to use console.log to send debugs to js land
 use web_sys::console;
 
 // using custom allocator which is built specifically for wasm; makes it smaller
-// + faster
+// + faster. Native builds keep the system allocator, which is better suited
+// for multiple threads.
+#[cfg(target_arch = "wasm32")]
 use wee_alloc;
+#[cfg(target_arch = "wasm32")]
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::string::String;
 
+mod extended_json;
+
 mod field;
 use crate::field::Field;
 
@@ -85,15 +93,76 @@ use crate::field_type::FieldType;
 mod value_type;
 use crate::value_type::ValueType;
 
+mod length_stats;
+use crate::length_stats::LengthStats;
+
+mod geo_stats;
+use crate::geo_stats::GeoStats;
+
+mod hyperloglog;
+use crate::hyperloglog::HyperLogLog;
+
+mod numeric_stats;
+use crate::numeric_stats::NumericStats;
+
+mod tdigest;
+use crate::tdigest::{Percentiles, TDigest};
+
+mod top_k;
+use crate::top_k::{SpaceSaving, TopValue};
+
+mod date_stats;
+pub use crate::date_stats::DateBucket;
+use crate::date_stats::DateStats;
+
+mod enum_values;
+use crate::enum_values::EnumCounter;
+
+mod semantic_type;
+
+mod string_stats;
+use crate::string_stats::StringStats;
+
+mod object_id_stats;
+use crate::object_id_stats::ObjectIdStats;
+
+mod options;
+pub use crate::options::SchemaOptions;
+
+mod reference;
+use crate::reference::Reference;
+
+mod relationships;
+pub use crate::relationships::{CollectionAnalyzer, Relationship};
+
+mod sampling;
+use crate::sampling::Rng;
+
+mod state;
+
+mod json_reader;
+pub use crate::json_reader::LineError;
+
+mod bson_reader;
+pub use crate::bson_reader::BsonReader;
+
+mod archive;
+
 // WASM Api of the Schema Parser.
 mod lib_wasm;
 use crate::lib_wasm::*;
 
+// Multi-threaded Api of the Schema Parser.
+#[cfg(feature = "parallel")]
+mod parallel;
+
 #[wasm_bindgen]
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct SchemaParser {
   count: usize,
   fields: HashMap<String, Field>,
+  #[serde(default, skip_serializing_if = "crate::state::skip")]
+  options: SchemaOptions,
 }
 
 impl SchemaParser {
@@ -107,9 +176,30 @@ impl SchemaParser {
   /// ```
   #[inline]
   pub fn new() -> Self {
+    Self::with_options(SchemaOptions::default())
+  }
+
+  /// Returns a new instance of Schema Parser, like `new()`, that analyses
+  /// documents according to the given options.
+  ///
+  /// # Arguments
+  /// * `options` - SchemaOptions, i.e. the maximum number of values to keep.
+  ///
+  /// # Examples
+  /// ```
+  /// use mongodb_schema_parser::{SchemaOptions, SchemaParser};
+  /// let options = SchemaOptions {
+  ///   max_values: Some(100),
+  ///   ..SchemaOptions::default()
+  /// };
+  /// let schema_parser = SchemaParser::with_options(options);
+  /// ```
+  #[inline]
+  pub fn with_options(options: SchemaOptions) -> Self {
     SchemaParser {
       count: 0,
       fields: HashMap::new(),
+      options,
     }
   }
 
@@ -128,45 +218,61 @@ impl SchemaParser {
   #[inline]
   pub fn write_json(&mut self, json: &str) -> Result<(), failure::Error> {
     let val: Value = serde_json::from_str(json)?;
-    let bson = Bson::from(val);
-    // should do a match for NoneError
-    let doc = bson
-      .as_document()
-      .ok_or_else(|| format_err!("Failed to parse bson"))?
-      .to_owned();
-    self.update_count();
-    self.generate_field(doc, None, None);
+    let doc = match extended_json::to_bson(val)? {
+      Bson::Document(doc) => doc,
+      _ => return Err(format_err!("Failed to parse bson")),
+    };
+    self.write_document(doc);
     Ok(())
   }
 
-  /// Writes Bson documents to SchemaParser's fields vector.
+  /// Writes a raw Bson document to SchemaParser's fields vector. `bytes` has
+  /// to hold exactly one document, see `write_bson_reader()` for streams of
+  /// documents.
+  ///
+  /// # Arguments
+  /// * `bytes` - A Bson encoded document, i.e. as returned by MongoDB.
+  ///
+  /// # Examples
+  /// ```
+  /// use mongodb_schema_parser::SchemaParser;
+  /// use bson::{bson, doc, encode_document};
+  ///
+  /// let mut bytes = Vec::new();
+  /// encode_document(&mut bytes, &doc! { "name": "Chashu", "type": "Cat" }).unwrap();
+  /// let mut schema_parser = SchemaParser::new();
+  /// schema_parser.write_bson(&bytes).unwrap();
+  /// ```
+  #[inline]
+  pub fn write_bson(&mut self, bytes: &[u8]) -> Result<(), failure::Error> {
+    // decode_document needs a byte stream that implements a reader, and u8
+    // slice does this.
+    let mut slice = bytes;
+    let doc = decode_document(&mut slice)?;
+    if !slice.is_empty() {
+      return Err(format_err!("Unexpected bytes after Bson document"));
+    }
+    self.write_document(doc);
+    Ok(())
+  }
+
+  /// Writes a Bson Document to SchemaParser's fields vector.
   ///
   /// # Arguments
   /// * `doc` - A Bson Document.
   ///
   /// # Examples
-  /// ```ignore
+  /// ```
   /// use mongodb_schema_parser::SchemaParser;
-  /// use js_sys::Uint8Array;
-  /// use bson::{doc, bson};
+  /// use bson::{bson, doc};
   ///
   /// let mut schema_parser = SchemaParser::new();
-  /// let uint8 = Uint8Array::new(&JsValue::from_str(r#"{ "name": "Chashu", "type": "Cat" }"#));
-  /// schema_parser.write_raw(uint8);
+  /// schema_parser.write_document(doc! { "name": "Chashu", "type": "Cat" });
   /// ```
   #[inline]
-  pub fn write_raw(&mut self, uint8: Uint8Array) -> Result<(), failure::Error> {
-    let mut decoded_vec = vec![0u8; uint8.length() as usize];
-    // fill up a new u8 vec with bytes we get from js; decode_document needs a
-    // byte stream that implements a reader and u8 slice does this.
-    uint8.copy_to(&mut decoded_vec);
-    let mut slice: &[u8] = &decoded_vec;
-    let doc = decode_document(&mut slice)?.to_owned();
-    // write bson internally
+  pub fn write_document(&mut self, doc: Document) {
     self.update_count();
     self.generate_field(doc, None, None);
-
-    Ok(())
   }
 
   /// Finalizes and returns SchemaParser struct -- result of all parsed
@@ -207,6 +313,174 @@ impl SchemaParser {
     Ok(serde_json::to_string(&schema)?)
   }
 
+  /// Merges another SchemaParser into this one, as if all of its documents
+  /// were written to this parser. This should be called before `flush()`, as
+  /// flushing recalculates probabilities, duplicates and unique values.
+  ///
+  /// # Arguments
+  /// * `other` - A SchemaParser that was fed a different set of documents.
+  ///
+  /// # Examples
+  /// ```
+  /// use mongodb_schema_parser::SchemaParser;
+  ///
+  /// let mut schema_parser = SchemaParser::new();
+  /// schema_parser.write_json(r#"{ "name": "Chashu", "type": "Cat" }"#);
+  /// let mut other = SchemaParser::new();
+  /// other.write_json(r#"{ "name": "Rey", "type": "Dog" }"#);
+  /// schema_parser.merge(other);
+  /// let schema = schema_parser.flush();
+  /// ```
+  pub fn merge(&mut self, other: SchemaParser) {
+    self.count += other.count;
+    for (key, field) in other.fields {
+      match self.fields.get_mut(&key) {
+        Some(existing) => existing.merge(field),
+        None => {
+          self.fields.insert(key, field);
+        }
+      }
+    }
+  }
+
+  /// Returns the full state of this SchemaParser as a json string: its
+  /// options, counts, kept values and the sketches used to estimate unique
+  /// values, percentiles and frequent values. Unlike the output of
+  /// `into_json()`, it can be turned back into a SchemaParser with
+  /// `from_state()`, i.e. to merge parsers that ran in separate processes.
+  ///
+  /// # Examples
+  /// ```
+  /// use mongodb_schema_parser::SchemaParser;
+  ///
+  /// let mut schema_parser = SchemaParser::new();
+  /// schema_parser.write_json(r#"{ "name": "Chashu", "type": "Cat" }"#);
+  /// let state = schema_parser.to_state().unwrap();
+  /// let mut other = SchemaParser::from_state(&state).unwrap();
+  /// other.write_json(r#"{ "name": "Rey", "type": "Dog" }"#);
+  /// ```
+  pub fn to_state(&self) -> Result<String, failure::Error> {
+    Ok(state::with_full_state(|| serde_json::to_string(self))?)
+  }
+
+  /// Creates a SchemaParser from a state returned by `to_state()`. It can be
+  /// written to, merged and flushed like the SchemaParser it was taken from.
+  ///
+  /// # Arguments
+  /// * `state` - A json string returned by `to_state()`.
+  pub fn from_state(state: &str) -> Result<Self, failure::Error> {
+    Ok(state::with_full_state(|| serde_json::from_str(state))?)
+  }
+
+  /// Returns a serde_json string of all fields keyed by their full dotted
+  /// path, including fields of nested documents. Like `into_json`, this
+  /// finalises the schema first.
+  ///
+  /// # Examples
+  /// ```
+  /// use mongodb_schema_parser::SchemaParser;
+  ///
+  /// let mut schema_parser = SchemaParser::new();
+  /// let json = r#"{ "name": "Chashu", "owner": { "name": "Irina" } }"#;
+  /// schema_parser.write_json(&json);
+  /// let schema = schema_parser.into_flat_json().unwrap();
+  /// println!("{}", schema);
+  /// ```
+  #[inline]
+  pub fn into_flat_json(mut self) -> Result<String, failure::Error> {
+    let schema = self.flush();
+    Ok(serde_json::to_string(&schema.into_flat_fields())?)
+  }
+
+  /// Returns a field given its full dotted path. Nested documents and arrays
+  /// of documents are looked up through their schemas.
+  ///
+  /// # Arguments
+  /// * `path` - A dotted path to a field. i.e `address.location.type`
+  ///
+  /// # Examples
+  /// ```
+  /// use mongodb_schema_parser::SchemaParser;
+  ///
+  /// let mut schema_parser = SchemaParser::new();
+  /// let json = r#"{ "name": "Chashu", "owner": { "name": "Irina" } }"#;
+  /// schema_parser.write_json(&json);
+  /// let field = schema_parser.get_field("owner.name").unwrap();
+  /// assert_eq!(field.path, "owner.name");
+  /// ```
+  pub fn get_field(&self, path: &str) -> Option<&Field> {
+    let mut segments = path.splitn(2, '.');
+    let field = self.fields.get(segments.next()?)?;
+    match segments.next() {
+      None => Some(field),
+      Some(rest) => field
+        .get_schemas()
+        .into_iter()
+        .find_map(|schema| schema.get_field(rest)),
+    }
+  }
+
+  /// Returns every field of this schema, including fields of nested
+  /// documents, keyed by their full dotted path. Fields don't hold the
+  /// schemas of their nested documents, as those are listed separately.
+  ///
+  /// # Examples
+  /// ```
+  /// use mongodb_schema_parser::SchemaParser;
+  ///
+  /// let mut schema_parser = SchemaParser::new();
+  /// let json = r#"{ "name": "Chashu", "owner": { "name": "Irina" } }"#;
+  /// schema_parser.write_json(&json);
+  /// let fields = schema_parser.flat_fields();
+  /// assert!(fields.contains_key("owner.name"));
+  /// ```
+  pub fn flat_fields(&self) -> BTreeMap<String, Field> {
+    self.clone().into_flat_fields()
+  }
+
+  /// Like `flat_fields()`, but consumes the SchemaParser instead of copying
+  /// its fields.
+  pub fn into_flat_fields(self) -> BTreeMap<String, Field> {
+    let mut fields = BTreeMap::new();
+    let mut parent_counts = BTreeMap::new();
+    let mut merged = BTreeSet::new();
+    self.collect_flat_fields(&mut fields, &mut parent_counts, &mut merged);
+    // a field holding both a Document and an Array of Documents has the same
+    // path in both of their schemas. Those fields were merged, and now need
+    // their probabilities set against both schemas' counts.
+    for path in merged {
+      if let Some(field) = fields.get_mut(&path) {
+        field.finalise_field(parent_counts[&path]);
+      }
+    }
+    fields
+  }
+
+  fn collect_flat_fields(
+    self,
+    fields: &mut BTreeMap<String, Field>,
+    parent_counts: &mut BTreeMap<String, usize>,
+    merged: &mut BTreeSet<String>,
+  ) {
+    let count = self.count;
+    for (_, mut field) in self.fields {
+      let schemas = field.take_schemas();
+      *parent_counts.entry(field.path.to_string()).or_insert(0) += count;
+      match fields.get_mut(&field.path) {
+        Some(existing) => {
+          merged.insert(field.path.to_string());
+          existing.merge(field);
+        }
+        None => {
+          fields.insert(field.path.to_string(), field);
+        }
+      }
+      for schema in schemas {
+        schema.collect_flat_fields(fields, parent_counts, merged);
+      }
+    }
+  }
+
   #[inline]
   fn generate_field(
     &mut self,
@@ -230,7 +504,7 @@ impl SchemaParser {
     if self.fields.contains_key(&key) {
       self.update_field(&key, value);
     } else {
-      let mut field = Field::new(key, path);
+      let mut field = Field::with_options(key, path, self.options);
       field.create_type(value);
       self.fields.insert(field.name.to_string(), field);
     }
@@ -240,40 +514,22 @@ impl SchemaParser {
   fn update_field(&mut self, key: &str, value: &Bson) {
     let field = self.fields.get_mut(key);
     if let Some(field) = field {
-      field.update_count();
-      if !field.does_field_type_exist(&value) {
-        // field type doesn't exist in field.types, create a new field_type
-        field.create_type(&value);
-      } else {
-        let type_val = FieldType::get_type(&value);
-        let field_type = field.types.get_mut(&type_val);
-        if let Some(field_type) = field_type {
-          field_type.update_type(&value);
-        }
-      }
+      field.update_type(value);
     }
   }
 
   #[inline]
   fn finalise_schema(&mut self) {
     for field in self.fields.values_mut() {
-      // If bson_types includes a Document, find that document and let its schema
-      // field update its own missing fields.
-      let field_type = field.types.get_mut(crate::field_type::DOCUMENT);
-      if let Some(field_type) = field_type {
-        let schema = &mut field_type.schema;
-        if let Some(schema) = schema {
-          return schema.finalise_schema();
-        }
-      }
-
       // create new field_types as Null for missing fields
       let missing = self.count - field.count;
       if missing > 0 {
         field.update_for_missing(missing);
       }
 
-      // check for duplicates, unique values, set probability
+      // check for duplicates, unique values, set probability. Field types
+      // holding a Document, or an Array of Documents, will let their schema
+      // update its own missing fields.
       field.finalise_field(self.count);
     }
   }
@@ -317,15 +573,43 @@ mod tests {
   //   bench.iter(|| schema_parser.write_json(&json_str));
   // }
 
-  // #[test]
-  // fn it_writes_bson() {
-  //   let mut schema_parser = SchemaParser::new();
-  //   // let bson_str = bson!({"name": "Nori", "type": "Cat"});
-  //   schema_parser.write_raw(&bson_str).unwrap();
-  //   println!("{:?}", schema_parser.flush());
-  //   assert_eq!(schema_parser.count, 1);
-  //   assert_eq!(schema_parser.fields.len(), 2);
-  // }
+  #[test]
+  fn it_writes_bson() {
+    let mut schema_parser = SchemaParser::new();
+    let mut bytes = Vec::new();
+    let doc = doc! { "name": "Nori", "type": "Cat" };
+    bson::encode_document(&mut bytes, &doc).unwrap();
+    schema_parser.write_bson(&bytes).unwrap();
+    assert_eq!(schema_parser.count, 1);
+    assert_eq!(schema_parser.fields.len(), 2);
+  }
+
+  #[test]
+  fn it_fails_on_invalid_bson() {
+    let mut schema_parser = SchemaParser::new();
+    assert!(schema_parser.write_bson(&[5, 0, 0]).is_err());
+    assert_eq!(schema_parser.count, 0);
+  }
+
+  #[test]
+  fn it_fails_on_trailing_bytes_after_bson() {
+    let mut schema_parser = SchemaParser::new();
+    let mut bytes = Vec::new();
+    let doc = doc! { "name": "Nori" };
+    bson::encode_document(&mut bytes, &doc).unwrap();
+    bson::encode_document(&mut bytes, &doc).unwrap();
+    assert!(schema_parser.write_bson(&bytes).is_err());
+    assert_eq!(schema_parser.count, 0);
+  }
+
+  #[test]
+  fn it_writes_document() {
+    let mut schema_parser = SchemaParser::new();
+    schema_parser.write_document(doc! { "name": "Nori", "age": 3 });
+    schema_parser.write_document(doc! { "name": "Rey" });
+    assert_eq!(schema_parser.count, 2);
+    assert_eq!(schema_parser.fields["name"].count, 2);
+  }
 
   // #[bench]
   // fn bench_it_creates_write_json(bench: &mut Bencher) {
@@ -501,11 +785,199 @@ mod tests {
       assert_eq!(field.types.len(), 1);
       let field_type = field.types.get("Array");
       if let Some(field_type) = field_type {
-        assert_eq!(field_type.values.len(), 4);
+        assert!(field_type.values.is_empty());
+        let items = field_type.items.as_ref().unwrap();
+        assert_eq!(items.count, 4);
+        assert_eq!(items.types.len(), 1);
+        assert_eq!(items.types["String"].values.len(), 4);
       }
     }
   }
 
+  #[test]
+  fn it_distinguishes_array_item_types() {
+    let mut schema_parser = SchemaParser::new();
+    let mixed_json = r#"{"tags": ["a", true, null]}"#;
+    let strings_json = r#"{"tags": ["b", "c"]}"#;
+    schema_parser.write_json(mixed_json).unwrap();
+    schema_parser.write_json(strings_json).unwrap();
+    let output = schema_parser.flush();
+    let field_type = &output.fields["tags"].types["Array"];
+    let items = field_type.items.as_ref().unwrap();
+    assert_eq!(items.count, 5);
+    assert_eq!(items.bson_types, vec!["String", "Boolean", "Null"]);
+    assert_eq!(items.types["String"].count, 3);
+    assert_eq!(items.types["Boolean"].count, 1);
+    assert_eq!(items.types["Null"].count, 1);
+  }
+
+  #[allow(clippy::float_cmp)]
+  #[test]
+  fn it_infers_schema_for_array_of_documents() {
+    let mut schema_parser = SchemaParser::new();
+    let json_str1 = r#"{"orders": [{"sku": "a1", "qty": 2}, {"sku": "b2"}]}"#;
+    let json_str2 = r#"{"orders": [{"sku": "c3", "gift": true}]}"#;
+    schema_parser.write_json(json_str1).unwrap();
+    schema_parser.write_json(json_str2).unwrap();
+    let output = schema_parser.flush();
+    let items = output.fields["orders"].types["Array"]
+      .items
+      .as_ref()
+      .unwrap();
+    assert_eq!(items.count, 3);
+
+    let doc = &items.types[crate::field_type::DOCUMENT];
+    assert_eq!(doc.count, 3);
+    let schema = doc.schema.as_ref().unwrap();
+    // counts are relative to the array elements, not to the parent documents
+    assert_eq!(schema.count, 3);
+    assert_eq!(schema.fields["sku"].count, 3);
+    assert_eq!(schema.fields["sku"].path, "orders.sku");
+    assert_eq!(schema.fields["sku"].probability, 1.0);
+    assert_eq!(schema.fields["gift"].types["Null"].count, 2);
+    assert_eq!(
+      schema.fields["gift"].types["Boolean"].probability,
+      1.0 / 3.0
+    );
+  }
+
+  #[test]
+  fn it_gets_field_by_path() {
+    let mut schema_parser = SchemaParser::new();
+    let json_str = r#"{"address": {"location": {"type": "Point"}}, "orders": [{"sku": "a1"}]}"#;
+    schema_parser.write_json(json_str).unwrap();
+    let field = schema_parser.get_field("address.location.type").unwrap();
+    assert_eq!(field.name, "type");
+    assert_eq!(field.path, "address.location.type");
+    let field = schema_parser.get_field("orders.sku").unwrap();
+    assert_eq!(field.path, "orders.sku");
+    assert!(schema_parser.get_field("address").is_some());
+    assert!(schema_parser.get_field("address.street").is_none());
+    assert!(schema_parser.get_field("orders.sku.id").is_none());
+  }
+
+  #[test]
+  fn it_flattens_fields() {
+    let mut schema_parser = SchemaParser::new();
+    let json_str =
+      r#"{"name": "Nori", "address": {"location": {"type": "Point"}}}"#;
+    schema_parser.write_json(json_str).unwrap();
+    let fields = schema_parser.flat_fields();
+    let paths: Vec<&String> = fields.keys().collect();
+    assert_eq!(
+      paths,
+      vec![
+        "address",
+        "address.location",
+        "address.location.type",
+        "name"
+      ]
+    );
+  }
+
+  #[test]
+  fn it_flattens_fields_without_schemas() {
+    let mut schema_parser = SchemaParser::new();
+    schema_parser.write_json(r#"{"a": {"b": 1}}"#).unwrap();
+    schema_parser.write_json(r#"{"a": [{"b": "x"}]}"#).unwrap();
+    let fields = schema_parser.flush().into_flat_fields();
+    assert!(fields["a"].types["Document"].schema.is_none());
+    let items = fields["a"].types["Array"].items.as_ref().unwrap();
+    assert!(items.types["Document"].schema.is_none());
+    // "a.b" is both in the Document's schema and the Array's
+    assert_eq!(fields["a.b"].count, 2);
+    assert_eq!(fields["a.b"].bson_types, vec!["Int", "String"]);
+    assert_eq!(fields["a.b"].probability, 1.0);
+  }
+
+  #[test]
+  fn it_caps_values() {
+    let options = SchemaOptions {
+      max_values: Some(5),
+      ..SchemaOptions::default()
+    };
+    let mut schema_parser = SchemaParser::with_options(options);
+    for i in 0..20 {
+      let json_str = format!(r#"{{"owner": {{"ids": [{}, {}]}}}}"#, i, i);
+      schema_parser.write_json(&json_str).unwrap();
+    }
+    let output = schema_parser.flush();
+    let field = output.get_field("owner.ids").unwrap();
+    let items = field.types["Array"].items.as_ref().unwrap();
+    let field_type = items.types.values().next().unwrap();
+    assert_eq!(field_type.count, 40);
+    assert_eq!(field_type.values.len(), 5);
+  }
+
+  #[test]
+  fn it_merges() {
+    let json_str1 =
+      r#"{"name": "Nori", "type": {"breed": "Norwegian Forest"}}"#;
+    let json_str2 = r#"{"name": "Rey", "toys": ["ball", 1.5]}"#;
+    let json_str3 = r#"{"name": "Chashu", "type": {"age": 3}, "toys": []}"#;
+
+    let mut schema_parser = SchemaParser::new();
+    schema_parser.write_json(json_str1).unwrap();
+    schema_parser.write_json(json_str2).unwrap();
+    schema_parser.write_json(json_str3).unwrap();
+
+    let mut merged = SchemaParser::new();
+    merged.write_json(json_str1).unwrap();
+    let mut other = SchemaParser::new();
+    other.write_json(json_str2).unwrap();
+    other.write_json(json_str3).unwrap();
+    merged.merge(other);
+
+    assert_eq!(merged.count, 3);
+    assert_eq!(merged.flush(), schema_parser.flush());
+  }
+
+  #[test]
+  fn it_merges_from_state() {
+    let options = SchemaOptions {
+      max_values: Some(2),
+      date_histogram: Some(DateBucket::Month),
+      ..SchemaOptions::default()
+    };
+    let jsons = [
+      r#"{"_id": {"$oid": "5d3a1e4a2f1b0c0001a1b2c3"}, "age": 3}"#,
+      r#"{"_id": {"$oid": "5d3a1e4a2f1b0c0001a1b2c4"}, "age": 3.5}"#,
+      r#"{"age": {"$numberLong": "3"}, "price": {"$numberDecimal": "1.5"}}"#,
+      r#"{"email": "nori@example.com", "born": {"$date": 1563000000000}}"#,
+      r#"{"email": "rey@example.com", "toys": [{"name": "ball"}, 1]}"#,
+    ];
+    let mut left = SchemaParser::with_options(options);
+    let mut right = SchemaParser::with_options(options);
+    for (i, json_str) in jsons.iter().enumerate() {
+      let parser = if i < 2 { &mut left } else { &mut right };
+      parser.write_json(json_str).unwrap();
+    }
+
+    let left_state = left.to_state().unwrap();
+    let right_state = right.to_state().unwrap();
+    let mut from_state = SchemaParser::from_state(&left_state).unwrap();
+    assert_eq!(from_state, left);
+    from_state.merge(SchemaParser::from_state(&right_state).unwrap());
+
+    left.merge(right);
+    assert_eq!(from_state.flush(), left.flush());
+  }
+
+  #[test]
+  fn it_keeps_nan_in_state() {
+    let mut schema_parser = SchemaParser::new();
+    schema_parser
+      .write_json(r#"{"ratio": {"$numberDouble": "NaN"}}"#)
+      .unwrap();
+    let state = schema_parser.to_state().unwrap();
+    let schema_parser = SchemaParser::from_state(&state).unwrap();
+    let values = &schema_parser.fields["ratio"].types["Double"].values;
+    match values[..] {
+      [ValueType::FloatingPoint(num)] => assert!(num.is_nan()),
+      _ => panic!("expected a NaN value, got {:?}", values),
+    }
+  }
+
   #[test]
   fn it_creates_different_field_types() {
     let mut schema_parser = SchemaParser::new();
@@ -41,14 +41,106 @@ impl SchemaParser {
     }
   }
 
+  /// Wrapper method for `schema_parser.write_json_array()` to be used in
+  /// JavaScript.
+  /// `wasm_bindgen(js_name = "writeJsonArray")`
+  ///
+  /// ```js, ignore
+  /// import { SchemaParser } from "mongodb-schema-parser"
+  ///
+  /// var schemaParser = new SchemaParser()
+  /// var json = "[{"name": "Nori", "type": "Cat"}, {"name": "Rey"}]"
+  /// schemaParser.writeJsonArray(json)
+  /// ````
+  #[wasm_bindgen(js_name = "writeJsonArray")]
+  pub fn wasm_write_json_array(&mut self, json: &str) -> Result<(), JsValue> {
+    match self.write_json_array(json.as_bytes()) {
+      Err(e) => Err(JsValue::from_str(&format!("{}", e))),
+      _ => Ok(()),
+    }
+  }
+
+  /// Wrapper method for `schema_parser.write_bson()` to be used in
+  /// JavaScript.
+  /// `wasm_bindgen(js_name = "writeRaw")`
+  ///
+  /// ```js, ignore
+  /// import { SchemaParser } from "mongodb-schema-parser"
+  ///
+  /// var schemaParser = new SchemaParser()
+  /// // i.e. a document fetched with the node driver's `raw` flag
+  /// schemaParser.writeRaw(bsonBuf)
+  /// ````
   #[wasm_bindgen(js_name = "writeRaw")]
   pub fn wasm_write_raw(&mut self, uint8: Uint8Array) -> Result<(), JsValue> {
-    match self.write_raw(uint8) {
+    // fill up a new u8 vec with bytes we get from js
+    let mut bytes = vec![0u8; uint8.length() as usize];
+    uint8.copy_to(&mut bytes);
+    match self.write_bson(&bytes) {
       Err(e) => Err(JsValue::from_str(&format!("{}", e))),
       _ => Ok(()),
     }
   }
 
+  /// Wrapper method for `schema_parser.merge()` to be used in JavaScript.
+  /// `wasm_bindgen(js_name = "merge")`
+  ///
+  /// Both parsers need to live in the same wasm instance. To merge a parser
+  /// from another web worker, pass its `toState()` to this worker and
+  /// rebuild it with `SchemaParser.fromState()`.
+  ///
+  /// ```js, ignore
+  /// import { SchemaParser } from "mongodb-schema-parser"
+  ///
+  /// var schemaParser = new SchemaParser()
+  /// schemaParser.writeJson("{"name": "Nori", "type": "Cat"}")
+  /// var other = new SchemaParser()
+  /// other.writeJson("{"name": "Rey", "type": "Dog"}")
+  /// schemaParser.merge(other)
+  /// ````
+  #[wasm_bindgen(js_name = "merge")]
+  pub fn wasm_merge(&mut self, other: SchemaParser) {
+    self.merge(other)
+  }
+
+  /// Wrapper method for `schema_parser.to_state()` to be used in JavaScript.
+  /// `wasm_bindgen(js_name = "toState")`
+  ///
+  /// ```js, ignore
+  /// import { SchemaParser } from "mongodb-schema-parser"
+  ///
+  /// var schemaParser = new SchemaParser()
+  /// schemaParser.writeJson("{"name": "Nori", "type": "Cat"}")
+  /// // i.e. post it from a web worker
+  /// var state = schemaParser.toState()
+  /// ````
+  #[wasm_bindgen(js_name = "toState")]
+  pub fn wasm_to_state(&self) -> Result<String, JsValue> {
+    match self.to_state() {
+      Err(e) => Err(JsValue::from_str(&format!("{}", e))),
+      Ok(val) => Ok(val),
+    }
+  }
+
+  /// Wrapper method for `SchemaParser::from_state()` to be used in
+  /// JavaScript.
+  /// `wasm_bindgen(js_name = "fromState")`
+  ///
+  /// ```js, ignore
+  /// import { SchemaParser } from "mongodb-schema-parser"
+  ///
+  /// var schemaParser = new SchemaParser()
+  /// // a state posted by a web worker
+  /// schemaParser.merge(SchemaParser.fromState(state))
+  /// ````
+  #[wasm_bindgen(js_name = "fromState")]
+  pub fn wasm_from_state(state: &str) -> Result<SchemaParser, JsValue> {
+    match Self::from_state(state) {
+      Err(e) => Err(JsValue::from_str(&format!("{}", e))),
+      Ok(val) => Ok(val),
+    }
+  }
+
   /// Wrapper method for `schema_parser.to_json()` to be used in JavaScript.
   /// `wasm_bindgen(js_name = "toJson")`
   ///
@@ -70,6 +162,28 @@ impl SchemaParser {
     }
   }
 
+  /// Wrapper method for `schema_parser.into_flat_json()` to be used in
+  /// JavaScript.
+  /// `wasm_bindgen(js_name = "toFlatJson")`
+  ///
+  /// ```js, ignore
+  /// import { SchemaParser } from "mongodb-schema-parser"
+  ///
+  /// var schemaParser = new SchemaParser()
+  /// var json = "{"name": "Nori", "owner": {"name": "Irina"}}"
+  /// schemaParser.writeJson(json)
+  /// // get every field keyed by its dotted path as a json string
+  /// var result = schemaParser.toFlatJson()
+  /// console.log(result) //
+  /// ````
+  #[wasm_bindgen(js_name = "toFlatJson")]
+  pub fn wasm_into_flat_json(self) -> Result<String, JsValue> {
+    match self.into_flat_json() {
+      Err(e) => Err(JsValue::from_str(&format!("{}", e))),
+      Ok(val) => Ok(val),
+    }
+  }
+
   /// Wrapper method for `schema_parser.to_json()` to be used in JavaScript.
   /// `wasm_bindgen(js_name = "toJson")`
   ///
@@ -0,0 +1,116 @@
+/// Summary statistics of numeric values. The mean and variance are updated one
+/// value at a time with Welford's algorithm, so no values need to be kept.
+///
+/// `variance` and `stddev` are the population variance and standard deviation.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NumericStats {
+  pub count: usize,
+  pub sum: f64,
+  pub min: f64,
+  pub max: f64,
+  pub mean: f64,
+  pub variance: f64,
+  pub stddev: f64,
+}
+
+impl NumericStats {
+  pub fn new(num: f64) -> Self {
+    let mut stats = NumericStats {
+      count: 0,
+      sum: 0.0,
+      min: num,
+      max: num,
+      mean: 0.0,
+      variance: 0.0,
+      stddev: 0.0,
+    };
+    stats.update(num);
+    stats
+  }
+
+  pub fn update(&mut self, num: f64) {
+    // sum of squared differences from the mean before this value
+    let m2 = self.variance * self.count as f64;
+    self.count += 1;
+    self.sum += num;
+    self.min = self.min.min(num);
+    self.max = self.max.max(num);
+
+    let delta = num - self.mean;
+    self.mean += delta / self.count as f64;
+    self.set_variance(m2 + delta * (num - self.mean));
+  }
+
+  pub fn merge(&mut self, other: &NumericStats) {
+    let count = self.count + other.count;
+    let m2 =
+      self.variance * self.count as f64 + other.variance * other.count as f64;
+    let delta = other.mean - self.mean;
+    let weight = self.count as f64 * other.count as f64 / count as f64;
+
+    self.mean += delta * other.count as f64 / count as f64;
+    self.count = count;
+    self.sum += other.sum;
+    self.min = self.min.min(other.min);
+    self.max = self.max.max(other.max);
+    self.set_variance(m2 + delta * delta * weight);
+  }
+
+  fn set_variance(&mut self, m2: f64) {
+    self.variance = m2 / self.count as f64;
+    self.stddev = self.variance.sqrt();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[allow(clippy::float_cmp)]
+  #[test]
+  fn it_creates_new() {
+    let stats = NumericStats::new(4.0);
+    assert_eq!(stats.count, 1);
+    assert_eq!(stats.sum, 4.0);
+    assert_eq!(stats.min, 4.0);
+    assert_eq!(stats.max, 4.0);
+    assert_eq!(stats.mean, 4.0);
+    assert_eq!(stats.variance, 0.0);
+  }
+
+  #[allow(clippy::float_cmp)]
+  #[test]
+  fn it_updates() {
+    let mut stats = NumericStats::new(2.0);
+    for num in &[4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+      stats.update(*num);
+    }
+    assert_eq!(stats.count, 8);
+    assert_eq!(stats.sum, 40.0);
+    assert_eq!(stats.min, 2.0);
+    assert_eq!(stats.max, 9.0);
+    assert_eq!(stats.mean, 5.0);
+    assert!((stats.variance - 4.0).abs() < 1e-9);
+    assert!((stats.stddev - 2.0).abs() < 1e-9);
+  }
+
+  #[allow(clippy::float_cmp)]
+  #[test]
+  fn it_merges() {
+    let mut stats = NumericStats::new(2.0);
+    for num in &[4.0, 4.0, 4.0] {
+      stats.update(*num);
+    }
+    let mut other = NumericStats::new(5.0);
+    for num in &[5.0, 7.0, 9.0] {
+      other.update(*num);
+    }
+    stats.merge(&other);
+    assert_eq!(stats.count, 8);
+    assert_eq!(stats.sum, 40.0);
+    assert_eq!(stats.min, 2.0);
+    assert_eq!(stats.max, 9.0);
+    assert_eq!(stats.mean, 5.0);
+    assert!((stats.variance - 4.0).abs() < 1e-9);
+  }
+}
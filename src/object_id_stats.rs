@@ -0,0 +1,98 @@
+use bson::oid::ObjectId;
+use chrono::{DateTime, TimeZone, Utc};
+use std::cmp;
+
+/// Creation times embedded in the ObjectIds seen for a field type, and
+/// whether the ObjectIds kept increasing in the order they were written.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ObjectIdStats {
+  pub min_timestamp: DateTime<Utc>,
+  pub max_timestamp: DateTime<Utc>,
+  pub monotonic: bool,
+  #[serde(default, skip_serializing_if = "crate::state::skip")]
+  first: [u8; 12],
+  #[serde(default, skip_serializing_if = "crate::state::skip")]
+  last: [u8; 12],
+}
+
+impl ObjectIdStats {
+  pub fn new(id: &ObjectId) -> Self {
+    let timestamp = Self::get_timestamp(id);
+    ObjectIdStats {
+      min_timestamp: timestamp,
+      max_timestamp: timestamp,
+      monotonic: true,
+      first: id.bytes(),
+      last: id.bytes(),
+    }
+  }
+
+  pub fn update(&mut self, id: &ObjectId) {
+    let timestamp = Self::get_timestamp(id);
+    self.min_timestamp = cmp::min(self.min_timestamp, timestamp);
+    self.max_timestamp = cmp::max(self.max_timestamp, timestamp);
+    // ObjectIds compare by their bytes: timestamp first, then counter
+    let bytes = id.bytes();
+    self.monotonic = self.monotonic && self.last < bytes;
+    self.last = bytes;
+  }
+
+  // `other` is expected to hold the ObjectIds written after ours, as is the
+  // case for a collection split into consecutive chunks.
+  pub fn merge(&mut self, other: &ObjectIdStats) {
+    self.min_timestamp = cmp::min(self.min_timestamp, other.min_timestamp);
+    self.max_timestamp = cmp::max(self.max_timestamp, other.max_timestamp);
+    self.monotonic =
+      self.monotonic && other.monotonic && self.last < other.first;
+    self.last = other.last;
+  }
+
+  fn get_timestamp(id: &ObjectId) -> DateTime<Utc> {
+    Utc.timestamp_opt(i64::from(id.timestamp()), 0).unwrap()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn object_id(id: &str) -> ObjectId {
+    ObjectId::with_string(id).unwrap()
+  }
+
+  #[test]
+  fn it_creates_new() {
+    let stats = ObjectIdStats::new(&object_id("50319491fe4dce143835c552"));
+    assert_eq!(stats.min_timestamp.timestamp(), 0x5031_9491);
+    assert_eq!(stats.max_timestamp.timestamp(), 0x5031_9491);
+    assert!(stats.monotonic);
+  }
+
+  #[test]
+  fn it_updates() {
+    let mut stats = ObjectIdStats::new(&object_id("50319491fe4dce143835c552"));
+    stats.update(&object_id("5031bb65fe4dce143635c960"));
+    assert!(stats.monotonic);
+    stats.update(&object_id("5031ce4bfe4dce143735c6df"));
+    assert_eq!(stats.min_timestamp.timestamp(), 0x5031_9491);
+    assert_eq!(stats.max_timestamp.timestamp(), 0x5031_ce4b);
+    assert!(stats.monotonic);
+    stats.update(&object_id("5031bb65fe4dce143635c961"));
+    assert_eq!(stats.max_timestamp.timestamp(), 0x5031_ce4b);
+    assert!(!stats.monotonic);
+  }
+
+  #[test]
+  fn it_merges() {
+    let mut stats = ObjectIdStats::new(&object_id("50319491fe4dce143835c552"));
+    let other = ObjectIdStats::new(&object_id("5031bb65fe4dce143635c960"));
+    stats.merge(&other);
+    assert_eq!(stats.max_timestamp.timestamp(), 0x5031_bb65);
+    assert!(stats.monotonic);
+
+    let mut other = ObjectIdStats::new(&object_id("50319491fe4dce143835c552"));
+    other.merge(&stats);
+    assert_eq!(other.min_timestamp.timestamp(), 0x5031_9491);
+    assert!(!other.monotonic);
+  }
+}
@@ -0,0 +1,58 @@
+/// Options for how a SchemaParser analyses the documents written to it. The
+/// same options are used by every nested schema, field and field type.
+///
+/// # Examples
+/// ```
+/// use mongodb_schema_parser::{SchemaOptions, SchemaParser};
+///
+/// let options = SchemaOptions {
+///   max_values: Some(1000),
+///   ..SchemaOptions::default()
+/// };
+/// let schema_parser = SchemaParser::with_options(options);
+/// ```
+use super::DateBucket;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct SchemaOptions {
+  /// Maximum number of values kept per field type. Once reached, values are
+  /// replaced using reservoir sampling, so that the kept values remain a
+  /// uniform sample of all values seen. `None` keeps every value.
+  pub max_values: Option<usize>,
+  /// Count unique values exactly by sorting all kept values when the schema
+  /// is flushed. By default, and once values are sampled, the number of
+  /// unique values is estimated with a HyperLogLog sketch instead, which
+  /// doesn't need to look at the kept values.
+  pub exact_unique: bool,
+  /// Number of most frequent values reported per field type. Four times as
+  /// many candidates are tracked, so that reported counts stay accurate. `0`
+  /// disables reporting frequent values.
+  pub top_k: usize,
+  /// A String field type with at most this many distinct values is an enum
+  /// candidate. `0` disables enum detection.
+  pub enum_max_values: usize,
+  /// An enum candidate's number of distinct values may be at most this
+  /// fraction of its number of values.
+  pub enum_max_ratio: f32,
+  /// Count the dates of UtcDatetime field types per day, month or year.
+  /// `None` only reports the range of dates.
+  pub date_histogram: Option<DateBucket>,
+  /// Seed of the random numbers used to sample values. Parsers that are
+  /// merged should be given different seeds, so that their samples are
+  /// independent of each other.
+  pub seed: u64,
+}
+
+impl Default for SchemaOptions {
+  fn default() -> Self {
+    SchemaOptions {
+      max_values: None,
+      exact_unique: false,
+      top_k: 10,
+      enum_max_values: 20,
+      enum_max_ratio: 0.1,
+      date_histogram: None,
+      seed: 0,
+    }
+  }
+}
@@ -0,0 +1,280 @@
+use super::{Document, SchemaOptions, SchemaParser};
+use rayon::prelude::*;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Multi-threaded API of the Schema Parser, enabled with the `parallel` feature.
+// Every rayon thread writes into its own SchemaParser; partial results are then
+// combined with `SchemaParser::merge()`.
+impl SchemaParser {
+  /// Infers a schema from json-like string slices using multiple threads.
+  ///
+  /// # Arguments
+  /// * `jsons` - Anything rayon can iterate over in parallel, i.e. a `Vec` or
+  /// a slice of json lines. Sequential iterators can be passed in with
+  /// rayon's `par_bridge()`.
+  ///
+  /// # Examples
+  /// ```
+  /// use mongodb_schema_parser::SchemaParser;
+  ///
+  /// let jsons = vec![
+  ///   r#"{ "name": "Chashu", "type": "Cat" }"#,
+  ///   r#"{ "name": "Rey", "type": "Dog" }"#,
+  /// ];
+  /// let mut schema_parser = SchemaParser::from_json_par(jsons).unwrap();
+  /// let schema = schema_parser.flush();
+  /// ```
+  pub fn from_json_par<I, S>(jsons: I) -> Result<Self, failure::Error>
+  where
+    I: IntoParallelIterator<Item = S>,
+    S: AsRef<str>,
+  {
+    Self::from_json_par_with_options(jsons, SchemaOptions::default())
+  }
+
+  /// Infers a schema from json-like string slices using multiple threads,
+  /// like `from_json_par()`, according to the given options.
+  ///
+  /// # Examples
+  /// ```
+  /// use mongodb_schema_parser::{SchemaOptions, SchemaParser};
+  ///
+  /// let jsons = vec![
+  ///   r#"{ "name": "Chashu", "type": "Cat" }"#,
+  ///   r#"{ "name": "Rey", "type": "Dog" }"#,
+  /// ];
+  /// let options = SchemaOptions {
+  ///   max_values: Some(1000),
+  ///   ..SchemaOptions::default()
+  /// };
+  /// let schema_parser =
+  ///   SchemaParser::from_json_par_with_options(jsons, options).unwrap();
+  /// ```
+  pub fn from_json_par_with_options<I, S>(
+    jsons: I,
+    options: SchemaOptions,
+  ) -> Result<Self, failure::Error>
+  where
+    I: IntoParallelIterator<Item = S>,
+    S: AsRef<str>,
+  {
+    Self::write_json_par(jsons.into_par_iter().map(Ok), options)
+  }
+
+  /// Infers a schema from lines of json using multiple threads, i.e. the
+  /// `lines()` of a `BufRead`. Lines are read on the calling thread, and
+  /// parsed on rayon's threads.
+  ///
+  /// # Arguments
+  /// * `lines` - An iterator of json lines, failing on I/O errors.
+  ///
+  /// # Examples
+  /// ```
+  /// use mongodb_schema_parser::SchemaParser;
+  /// use std::io::{BufRead, Cursor};
+  ///
+  /// let file = Cursor::new("{\"name\": \"Chashu\"}\n{\"name\": \"Rey\"}");
+  /// let schema_parser = SchemaParser::from_lines_par(file.lines()).unwrap();
+  /// ```
+  pub fn from_lines_par<I>(lines: I) -> Result<Self, failure::Error>
+  where
+    I: IntoIterator<Item = io::Result<String>>,
+    I::IntoIter: Send,
+  {
+    Self::from_lines_par_with_options(lines, SchemaOptions::default())
+  }
+
+  /// Infers a schema from lines of json using multiple threads, like
+  /// `from_lines_par()`, according to the given options.
+  pub fn from_lines_par_with_options<I>(
+    lines: I,
+    options: SchemaOptions,
+  ) -> Result<Self, failure::Error>
+  where
+    I: IntoIterator<Item = io::Result<String>>,
+    I::IntoIter: Send,
+  {
+    Self::write_json_par(lines.into_iter().par_bridge(), options)
+  }
+
+  fn write_json_par<I, S>(
+    jsons: I,
+    options: SchemaOptions,
+  ) -> Result<Self, failure::Error>
+  where
+    I: ParallelIterator<Item = io::Result<S>>,
+    S: AsRef<str>,
+  {
+    let seeds = AtomicU64::new(options.seed);
+    let new = || Self::with_worker_seed(options, &seeds);
+    jsons
+      .try_fold(new, |mut schema_parser, json| {
+        schema_parser.write_json(json?.as_ref())?;
+        Ok::<_, failure::Error>(schema_parser)
+      })
+      .try_reduce(new, |mut schema_parser, other| {
+        schema_parser.merge(other);
+        Ok(schema_parser)
+      })
+  }
+
+  /// Infers a schema from Bson documents using multiple threads.
+  ///
+  /// # Arguments
+  /// * `docs` - Anything rayon can iterate over in parallel, yielding Bson
+  /// Documents.
+  ///
+  /// # Examples
+  /// ```
+  /// use mongodb_schema_parser::SchemaParser;
+  /// use bson::{doc, bson};
+  ///
+  /// let docs = vec![
+  ///   doc! { "name": "Chashu", "type": "Cat" },
+  ///   doc! { "name": "Rey", "type": "Dog" },
+  /// ];
+  /// let mut schema_parser = SchemaParser::from_documents_par(docs);
+  /// let schema = schema_parser.flush();
+  /// ```
+  pub fn from_documents_par<I>(docs: I) -> Self
+  where
+    I: IntoParallelIterator<Item = Document>,
+  {
+    Self::from_documents_par_with_options(docs, SchemaOptions::default())
+  }
+
+  /// Infers a schema from Bson documents using multiple threads, like
+  /// `from_documents_par()`, according to the given options.
+  pub fn from_documents_par_with_options<I>(
+    docs: I,
+    options: SchemaOptions,
+  ) -> Self
+  where
+    I: IntoParallelIterator<Item = Document>,
+  {
+    let seeds = AtomicU64::new(options.seed);
+    let new = || Self::with_worker_seed(options, &seeds);
+    docs
+      .into_par_iter()
+      .fold(new, |mut schema_parser, doc| {
+        schema_parser.write_document(doc);
+        schema_parser
+      })
+      .reduce(new, |mut schema_parser, other| {
+        schema_parser.merge(other);
+        schema_parser
+      })
+  }
+
+  // Every worker samples values with its own seed, so that merging doesn't
+  // combine samples taken with the same random numbers.
+  fn with_worker_seed(options: SchemaOptions, seeds: &AtomicU64) -> Self {
+    let seed = seeds.fetch_add(1, Ordering::Relaxed);
+    SchemaParser::with_options(SchemaOptions { seed, ..options })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use bson::{bson, doc};
+  use std::io::{BufRead, Cursor};
+
+  #[test]
+  fn it_writes_json_in_parallel() {
+    let jsons: Vec<String> = (0..100)
+      .map(|i| match i % 25 {
+        0 => format!(r#"{{"name": "Nori", "age": "{}"}}"#, i),
+        _ => format!(
+          r#"{{"name": "Nori", "age": {}, "owner": {{"age": {}}}}}"#,
+          i % 10,
+          i
+        ),
+      })
+      .collect();
+    let mut schema_parser = SchemaParser::new();
+    for json in &jsons {
+      schema_parser.write_json(json).unwrap();
+    }
+    let mut parallel = SchemaParser::from_json_par(&jsons).unwrap();
+    assert_eq!(parallel.count, 100);
+
+    let fields = schema_parser.flush().into_flat_fields();
+    let parallel_fields = parallel.flush().into_flat_fields();
+    assert_eq!(
+      parallel_fields.keys().collect::<Vec<_>>(),
+      fields.keys().collect::<Vec<_>>()
+    );
+    for (path, field) in fields {
+      let parallel_field = &parallel_fields[&path];
+      assert_eq!(parallel_field.count, field.count);
+      assert_eq!(parallel_field.bson_types, field.bson_types);
+      for (bson_type, field_type) in &field.types {
+        let parallel_field_type = &parallel_field.types[bson_type];
+        assert_eq!(parallel_field_type.count, field_type.count);
+        assert_eq!(parallel_field_type.unique, field_type.unique);
+      }
+    }
+  }
+
+  #[test]
+  fn it_writes_lines_in_parallel() {
+    let file: String = (0..100)
+      .map(|i| format!("{{\"name\": \"Nori\", \"age\": {}}}\n", i))
+      .collect();
+    let schema_parser =
+      SchemaParser::from_lines_par(Cursor::new(file).lines()).unwrap();
+    assert_eq!(schema_parser.count, 100);
+    assert_eq!(schema_parser.fields["age"].types["Int"].count, 100);
+  }
+
+  #[test]
+  fn it_fails_on_invalid_lines_in_parallel() {
+    let lines = vec![
+      Ok(r#"{"name": "Nori"}"#.to_string()),
+      Err(io::Error::new(io::ErrorKind::Other, "disconnected")),
+    ];
+    assert!(SchemaParser::from_lines_par(lines).is_err());
+  }
+
+  #[test]
+  fn it_seeds_workers_differently() {
+    let options = SchemaOptions::default();
+    let seeds = AtomicU64::new(options.seed);
+    let first = SchemaParser::with_worker_seed(options, &seeds);
+    let second = SchemaParser::with_worker_seed(options, &seeds);
+    assert_ne!(first.options.seed, second.options.seed);
+  }
+
+  #[test]
+  fn it_fails_on_invalid_json_in_parallel() {
+    let jsons = vec![r#"{"name": "Nori"}"#, r#"{"name": "#];
+    assert!(SchemaParser::from_json_par(jsons).is_err());
+  }
+
+  #[test]
+  fn it_writes_json_in_parallel_with_options() {
+    let jsons: Vec<String> = (0..1000)
+      .map(|i| format!(r#"{{"name": "Nori", "age": {}}}"#, i))
+      .collect();
+    let options = SchemaOptions {
+      max_values: Some(10),
+      ..SchemaOptions::default()
+    };
+    let schema_parser =
+      SchemaParser::from_json_par_with_options(&jsons, options).unwrap();
+    let age = &schema_parser.fields["age"].types["Int"];
+    assert_eq!(age.count, 1000);
+    assert_eq!(age.values.len(), 10);
+  }
+
+  #[test]
+  fn it_writes_documents_in_parallel() {
+    let docs: Vec<Document> =
+      (0..100).map(|i| doc! { "name": "Rey", "age": i }).collect();
+    let schema_parser = SchemaParser::from_documents_par(docs);
+    assert_eq!(schema_parser.count, 100);
+    assert_eq!(schema_parser.fields["age"].count, 100);
+  }
+}
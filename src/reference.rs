@@ -0,0 +1,110 @@
+use bson::{Bson, Document};
+use std::collections::BTreeMap;
+
+pub static DBREF: &str = "DBRef";
+pub static MANUAL: &str = "Manual";
+
+/// A field type referencing documents in other collections: either DBRef
+/// subdocuments, or ObjectIds in a field named like one, i.e. `user_id`.
+///
+/// `collections` counts the referenced namespaces where they are known,
+/// which is only the case for DBRefs.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Reference {
+  pub kind: String,
+  pub count: usize,
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub collections: BTreeMap<String, usize>,
+}
+
+impl Reference {
+  pub fn new(kind: &str) -> Self {
+    Reference {
+      kind: kind.to_string(),
+      count: 0,
+      collections: BTreeMap::new(),
+    }
+  }
+
+  pub fn update(&mut self, collection: Option<String>) {
+    self.count += 1;
+    if let Some(collection) = collection {
+      *self.collections.entry(collection).or_insert(0) += 1;
+    }
+  }
+
+  pub fn merge(&mut self, other: &Reference) {
+    self.count += other.count;
+    for (collection, count) in &other.collections {
+      *self.collections.entry(collection.to_string()).or_insert(0) += count;
+    }
+  }
+
+  /// Namespace referenced by a DBRef, i.e. `{ "$ref": "users", "$id": .. }`,
+  /// prefixed with its database when `$db` is set.
+  pub fn get_dbref(doc: &Document) -> Option<String> {
+    if !doc.contains_key("$id") {
+      return None;
+    }
+    match (doc.get("$ref"), doc.get("$db")) {
+      (Some(Bson::String(coll)), Some(Bson::String(db))) => {
+        Some(format!("{}.{}", db, coll))
+      }
+      (Some(Bson::String(coll)), None) => Some(coll.to_string()),
+      _ => None,
+    }
+  }
+
+  /// Whether a field holding ObjectIds is named like a reference, i.e.
+  /// `user_id`, `userId` or `user_ids`. A document's own `_id` is not one.
+  pub fn is_reference_name(name: &str) -> bool {
+    let suffixes = ["_id", "Id", "_ids", "Ids"];
+    suffixes
+      .iter()
+      .any(|suffix| name.len() > suffix.len() && name.ends_with(suffix))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use bson::oid::ObjectId;
+  use bson::{bson, doc};
+
+  #[test]
+  fn it_gets_dbref() {
+    let id = || ObjectId::with_string("50319491fe4dce143835c552").unwrap();
+    let dbref = doc! { "$ref": "users", "$id": id() };
+    assert_eq!(Reference::get_dbref(&dbref), Some("users".to_string()));
+    let dbref = doc! { "$ref": "users", "$id": id(), "$db": "fanclub" };
+    assert_eq!(
+      Reference::get_dbref(&dbref),
+      Some("fanclub.users".to_string())
+    );
+    let doc = doc! { "$ref": "users" };
+    assert_eq!(Reference::get_dbref(&doc), None);
+  }
+
+  #[test]
+  fn it_checks_reference_name() {
+    assert!(Reference::is_reference_name("user_id"));
+    assert!(Reference::is_reference_name("userId"));
+    assert!(Reference::is_reference_name("order_ids"));
+    assert!(!Reference::is_reference_name("_id"));
+    assert!(!Reference::is_reference_name("Id"));
+    assert!(!Reference::is_reference_name("user"));
+  }
+
+  #[test]
+  fn it_merges() {
+    let mut reference = Reference::new(DBREF);
+    reference.update(Some("users".to_string()));
+    let mut other = Reference::new(DBREF);
+    other.update(Some("users".to_string()));
+    other.update(Some("orders".to_string()));
+    reference.merge(&other);
+    assert_eq!(reference.count, 3);
+    assert_eq!(reference.collections["users"], 2);
+    assert_eq!(reference.collections["orders"], 1);
+  }
+}
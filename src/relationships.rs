@@ -0,0 +1,342 @@
+use super::{field_type, FieldType, SchemaOptions, SchemaParser};
+use std::collections::{BTreeMap, HashSet};
+
+// A few values can match another collection's `_id`s by chance, i.e. short
+// strings, so a relationship needs at least this many matched values, and
+// at least this confidence.
+const MIN_MATCHED: usize = 2;
+const MIN_CONFIDENCE: f32 = 0.5;
+
+/// A field that probably references documents of another collection, found
+/// by looking up the field's values among the other collection's `_id`s.
+/// ObjectIds only match ObjectIds, and strings only match strings.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Relationship {
+  pub collection: String,
+  pub path: String,
+  pub bson_type: String,
+  pub referenced_collection: String,
+  /// Number of distinct values of the field that were looked up.
+  pub sampled: usize,
+  /// Number of those values that are an `_id` in the referenced collection.
+  pub matched: usize,
+  /// Fraction of looked up values that were found, corrected for `_id`s
+  /// that weren't kept because of `SchemaOptions::max_values`.
+  pub confidence: f32,
+}
+
+/// Analyses several collections of a database at once: every collection gets
+/// its own SchemaParser, and flushing the analyzer additionally looks for
+/// relationships between collections.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CollectionAnalyzer {
+  pub collections: BTreeMap<String, SchemaParser>,
+  pub relationships: Vec<Relationship>,
+  #[serde(skip)]
+  options: SchemaOptions,
+}
+
+// `_id` values of a collection, and the fraction of `_id`s they make up.
+struct Ids {
+  hashes: HashSet<u64>,
+  coverage: f32,
+}
+
+impl CollectionAnalyzer {
+  /// Returns a new CollectionAnalyzer without any collections.
+  ///
+  /// # Examples
+  /// ```
+  /// use mongodb_schema_parser::CollectionAnalyzer;
+  /// let analyzer = CollectionAnalyzer::new();
+  /// ```
+  pub fn new() -> Self {
+    Self::with_options(SchemaOptions::default())
+  }
+
+  /// Returns a new CollectionAnalyzer, like `new()`, whose collections are
+  /// analysed according to the given options.
+  pub fn with_options(options: SchemaOptions) -> Self {
+    CollectionAnalyzer {
+      collections: BTreeMap::new(),
+      relationships: Vec::new(),
+      options,
+    }
+  }
+
+  /// Returns the SchemaParser of a collection, creating it if it doesn't
+  /// exist yet.
+  ///
+  /// # Arguments
+  /// * `name` - The collection's name.
+  pub fn collection(&mut self, name: &str) -> &mut SchemaParser {
+    let options = self.options;
+    self
+      .collections
+      .entry(name.to_string())
+      .or_insert_with(|| SchemaParser::with_options(options))
+  }
+
+  /// Writes a json-like string slice to a collection's SchemaParser.
+  ///
+  /// # Arguments
+  /// * `collection` - The collection's name.
+  /// * `json` - A json-like string slice. i.e `{ "name": "Nori", "type": "Cat"}`
+  ///
+  /// # Examples
+  /// ```
+  /// use mongodb_schema_parser::CollectionAnalyzer;
+  ///
+  /// let mut analyzer = CollectionAnalyzer::new();
+  /// analyzer.write_json("pets", r#"{ "_id": "chashu", "type": "Cat" }"#);
+  /// analyzer.write_json("owners", r#"{ "name": "Irina", "pet": "chashu" }"#);
+  /// ```
+  pub fn write_json(
+    &mut self,
+    collection: &str,
+    json: &str,
+  ) -> Result<(), failure::Error> {
+    self.collection(collection).write_json(json)
+  }
+
+  /// Adds an already populated SchemaParser for a collection. It is merged
+  /// into the collection's SchemaParser if there already is one.
+  pub fn add_collection(&mut self, name: &str, schema_parser: SchemaParser) {
+    self.collection(name).merge(schema_parser);
+  }
+
+  /// Finalizes the schema of every collection, finds relationships between
+  /// them and returns the result.
+  ///
+  /// # Examples
+  /// ```
+  /// use mongodb_schema_parser::CollectionAnalyzer;
+  ///
+  /// let mut analyzer = CollectionAnalyzer::new();
+  /// analyzer.write_json("pets", r#"{ "_id": "chashu", "type": "Cat" }"#);
+  /// analyzer.write_json("pets", r#"{ "_id": "rey", "type": "Dog" }"#);
+  /// analyzer.write_json("owners", r#"{ "name": "Irina", "pet": "chashu" }"#);
+  /// analyzer.write_json("owners", r#"{ "name": "Lucas", "pet": "rey" }"#);
+  /// let result = analyzer.flush();
+  /// assert_eq!(result.relationships[0].path, "pet");
+  /// ```
+  pub fn flush(&mut self) -> CollectionAnalyzer {
+    for schema_parser in self.collections.values_mut() {
+      schema_parser.finalise_schema();
+    }
+    self.relationships = self.find_relationships();
+    self.to_owned()
+  }
+
+  /// Returns a serde_json string of the flushed result.
+  pub fn into_json(mut self) -> Result<String, failure::Error> {
+    let result = self.flush();
+    Ok(serde_json::to_string(&result)?)
+  }
+
+  fn find_relationships(&self) -> Vec<Relationship> {
+    let ids: BTreeMap<&str, Ids> = self
+      .collections
+      .iter()
+      .filter_map(|(name, schema_parser)| {
+        Self::get_ids(schema_parser).map(|ids| (name.as_str(), ids))
+      })
+      .collect();
+
+    let mut relationships = Vec::new();
+    for (collection, schema_parser) in &self.collections {
+      for (path, field) in schema_parser.flat_fields() {
+        // a collection's `_id`s trivially match themselves
+        if path == "_id" {
+          continue;
+        }
+        for field_type in Self::get_key_types(field.types.values()) {
+          let hashes: HashSet<u64> =
+            field_type.values.iter().map(|v| v.stable_hash()).collect();
+          if hashes.is_empty() {
+            continue;
+          }
+          for (referenced_collection, ids) in &ids {
+            let matched = hashes.intersection(&ids.hashes).count();
+            let ratio = matched as f32 / hashes.len() as f32;
+            let confidence = (ratio / ids.coverage).min(1.0);
+            if matched < MIN_MATCHED || confidence < MIN_CONFIDENCE {
+              continue;
+            }
+            relationships.push(Relationship {
+              collection: collection.to_string(),
+              path: path.to_string(),
+              bson_type: field_type.bson_type.to_string(),
+              referenced_collection: referenced_collection.to_string(),
+              sampled: hashes.len(),
+              matched,
+              confidence,
+            });
+          }
+        }
+      }
+    }
+    relationships.sort_by(|a, b| {
+      b.confidence
+        .partial_cmp(&a.confidence)
+        .unwrap()
+        .then_with(|| a.collection.cmp(&b.collection))
+        .then_with(|| a.path.cmp(&b.path))
+    });
+    relationships
+  }
+
+  // ObjectId and String field types, including those of array elements, as
+  // these are the types commonly used for `_id`s.
+  fn get_key_types<'a>(
+    field_types: impl Iterator<Item = &'a FieldType>,
+  ) -> Vec<&'a FieldType> {
+    let mut key_types = Vec::new();
+    for field_type in field_types {
+      if Self::is_key_type(field_type) {
+        key_types.push(field_type);
+      }
+      if let Some(items) = &field_type.items {
+        key_types.extend(items.types.values().filter(|t| Self::is_key_type(t)));
+      }
+    }
+    key_types
+  }
+
+  fn is_key_type(field_type: &FieldType) -> bool {
+    field_type.bson_type == field_type::OBJECTID
+      || field_type.bson_type == field_type::STRING
+  }
+
+  fn get_ids(schema_parser: &SchemaParser) -> Option<Ids> {
+    let id = schema_parser.fields.get("_id")?;
+    let mut hashes = HashSet::new();
+    let mut count = 0;
+    for field_type in id.types.values().filter(|t| Self::is_key_type(t)) {
+      hashes.extend(field_type.values.iter().map(|v| v.stable_hash()));
+      count += field_type.count;
+    }
+    if hashes.is_empty() {
+      return None;
+    }
+    Some(Ids {
+      coverage: (hashes.len() as f32 / count as f32).min(1.0),
+      hashes,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn analyzer() -> CollectionAnalyzer {
+    let mut analyzer = CollectionAnalyzer::new();
+    let members = [
+      r#"{"_id": {"$oid": "50319491fe4dce143835c552"}, "name": "Ellie"}"#,
+      r#"{"_id": {"$oid": "5031bb65fe4dce143635c960"}, "name": "Ava"}"#,
+    ];
+    for json in &members {
+      analyzer.write_json("members", json).unwrap();
+    }
+    let orders = [
+      r#"{"member_id": {"$oid": "50319491fe4dce143835c552"}, "sku": "Ava"}"#,
+      r#"{"member_id": {"$oid": "5031bb65fe4dce143635c960"}, "sku": "a-1"}"#,
+      r#"{"member_id": {"$oid": "5031ce4bfe4dce143735c6df"}, "sku": "a-2"}"#,
+    ];
+    for json in &orders {
+      analyzer.write_json("orders", json).unwrap();
+    }
+    analyzer
+  }
+
+  #[allow(clippy::float_cmp)]
+  #[test]
+  fn it_finds_relationships() {
+    let result = analyzer().flush();
+    assert_eq!(result.collections.len(), 2);
+    assert_eq!(result.relationships.len(), 1);
+    let relationship = &result.relationships[0];
+    assert_eq!(relationship.collection, "orders");
+    assert_eq!(relationship.path, "member_id");
+    assert_eq!(relationship.bson_type, "ObjectId");
+    assert_eq!(relationship.referenced_collection, "members");
+    assert_eq!(relationship.sampled, 3);
+    assert_eq!(relationship.matched, 2);
+    assert_eq!(relationship.confidence, 2.0 / 3.0);
+  }
+
+  #[test]
+  fn it_finds_relationships_in_arrays() {
+    let mut analyzer = analyzer();
+    let json = r#"{"members": [
+      {"$oid": "50319491fe4dce143835c552"},
+      {"$oid": "5031bb65fe4dce143635c960"}
+    ]}"#;
+    analyzer.write_json("clubs", json).unwrap();
+    let result = analyzer.flush();
+    let relationship = result
+      .relationships
+      .iter()
+      .find(|relationship| relationship.collection == "clubs")
+      .unwrap();
+    assert_eq!(relationship.path, "members");
+    assert_eq!(relationship.referenced_collection, "members");
+    assert_eq!(relationship.matched, 2);
+  }
+
+  #[test]
+  fn it_ignores_single_matches() {
+    let mut analyzer = analyzer();
+    let json = r#"{"founder": {"$oid": "50319491fe4dce143835c552"}}"#;
+    analyzer.write_json("clubs", json).unwrap();
+    let result = analyzer.flush();
+    assert!(result
+      .relationships
+      .iter()
+      .all(|relationship| relationship.collection != "clubs"));
+  }
+
+  #[test]
+  fn it_tells_apart_object_ids_and_strings() {
+    let mut analyzer = analyzer();
+    for id in &["50319491fe4dce143835c552", "5031bb65fe4dce143635c960"] {
+      let json = format!(r#"{{"member": "{}"}}"#, id);
+      analyzer.write_json("clubs", &json).unwrap();
+    }
+    let result = analyzer.flush();
+    assert!(result
+      .relationships
+      .iter()
+      .all(|relationship| relationship.collection != "clubs"));
+  }
+
+  #[allow(clippy::float_cmp)]
+  #[test]
+  fn it_corrects_confidence_for_sampled_ids() {
+    let mut pets = SchemaParser::with_options(SchemaOptions {
+      max_values: Some(2),
+      ..SchemaOptions::default()
+    });
+    let names = ["chashu", "rey", "nori", "kuma"];
+    for name in &names {
+      let json = format!(r#"{{"_id": "{}"}}"#, name);
+      pets.write_json(&json).unwrap();
+    }
+    let mut analyzer = CollectionAnalyzer::new();
+    analyzer.add_collection("pets", pets);
+    for name in &names {
+      let json = format!(r#"{{"pet": "{}"}}"#, name);
+      analyzer.write_json("owners", &json).unwrap();
+    }
+    // the owners keep all of their values, so whichever pet ids were kept
+    // are found
+    let result = analyzer.flush();
+    let relationship = &result.relationships[0];
+    assert_eq!(relationship.sampled, 4);
+    assert_eq!(relationship.matched, 2);
+    // half of the pets' ids were kept, so finding half of the owners' pets
+    // is as good as finding all of them
+    assert_eq!(relationship.confidence, 1.0);
+  }
+}
@@ -0,0 +1,166 @@
+/// A small xorshift* pseudo random number generator. Sampling only needs to be
+/// uniform, not unpredictable, and this way wasm builds don't need a source of
+/// OS randomness.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Rng {
+  state: u64,
+}
+
+impl Default for Rng {
+  fn default() -> Self {
+    Rng {
+      state: 0x853c_49e6_748f_ea9b,
+    }
+  }
+}
+
+impl Rng {
+  /// Returns a generator whose numbers depend on `seed`. Seed `0` gives the
+  /// default generator, and nearby seeds give unrelated numbers.
+  pub fn with_seed(seed: u64) -> Self {
+    // murmur3's finalizer, which maps 0 to itself
+    let mut mix = seed;
+    mix = (mix ^ (mix >> 33)).wrapping_mul(0xff51_afd7_ed55_8ccd);
+    mix = (mix ^ (mix >> 33)).wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    mix ^= mix >> 33;
+    match Rng::default().state ^ mix {
+      // xorshift gets stuck on a zero state
+      0 => Rng::default(),
+      state => Rng { state },
+    }
+  }
+
+  pub fn next_u64(&mut self) -> u64 {
+    self.state ^= self.state >> 12;
+    self.state ^= self.state << 25;
+    self.state ^= self.state >> 27;
+    self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+  }
+
+  /// Returns a number in `[0, bound)`. `bound` must not be zero.
+  pub fn gen_range(&mut self, bound: usize) -> usize {
+    (self.next_u64() % bound as u64) as usize
+  }
+}
+
+/// Offers `value` to a reservoir of at most `max` values. `seen` is the number
+/// of values offered so far, including this one.
+pub fn sample<T>(
+  values: &mut Vec<T>,
+  value: T,
+  seen: usize,
+  max: usize,
+  rng: &mut Rng,
+) {
+  if values.len() < max {
+    values.push(value);
+  } else {
+    // keep the new value with a probability of max / seen
+    let index = rng.gen_range(seen);
+    if index < max {
+      values[index] = value;
+    }
+  }
+}
+
+/// Merges two reservoirs into a reservoir of at most `max` values, so that it
+/// is a uniform sample of the `left_seen + right_seen` values both of them
+/// were offered.
+pub fn merge_samples<T>(
+  mut left: Vec<T>,
+  mut left_seen: usize,
+  mut right: Vec<T>,
+  mut right_seen: usize,
+  max: usize,
+  rng: &mut Rng,
+) -> Vec<T> {
+  if left.len() + right.len() <= max {
+    left.extend(right);
+    return left;
+  }
+
+  let mut merged = Vec::with_capacity(max);
+  while merged.len() < max && !(left.is_empty() && right.is_empty()) {
+    // every value seen by a side is equally likely to be picked next
+    let take_left = right.is_empty()
+      || (!left.is_empty()
+        && rng.gen_range(left_seen + right_seen) < left_seen);
+    let (values, seen) = if take_left {
+      (&mut left, &mut left_seen)
+    } else {
+      (&mut right, &mut right_seen)
+    };
+    let index = rng.gen_range(values.len());
+    merged.push(values.swap_remove(index));
+    *seen -= 1;
+  }
+  merged
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn it_generates_in_range() {
+    let mut rng = Rng::default();
+    for _ in 0..1000 {
+      assert!(rng.gen_range(7) < 7);
+    }
+  }
+
+  #[test]
+  fn it_seeds_generators() {
+    assert_eq!(Rng::with_seed(0), Rng::default());
+    let mut rng = Rng::with_seed(1);
+    let mut other = Rng::with_seed(2);
+    assert_ne!(rng.next_u64(), other.next_u64());
+  }
+
+  #[test]
+  fn it_samples_up_to_max() {
+    let mut rng = Rng::default();
+    let mut values = Vec::new();
+    for seen in 1..=1000 {
+      sample(&mut values, seen, seen, 10, &mut rng);
+    }
+    assert_eq!(values.len(), 10);
+  }
+
+  #[test]
+  fn it_samples_uniformly() {
+    let mut rng = Rng::default();
+    let mut low = 0;
+    for _ in 0..200 {
+      let mut values = Vec::new();
+      for seen in 1..=100 {
+        sample(&mut values, seen, seen, 10, &mut rng);
+      }
+      low += values.iter().filter(|value| **value <= 50).count();
+    }
+    // about half of the 2000 sampled values should come from the first half
+    assert!(low > 800 && low < 1200);
+  }
+
+  #[test]
+  fn it_merges_small_samples() {
+    let mut rng = Rng::default();
+    let merged = merge_samples(vec![1, 2], 2, vec![3], 1, 10, &mut rng);
+    assert_eq!(merged, vec![1, 2, 3]);
+  }
+
+  #[test]
+  fn it_merges_samples_by_weight() {
+    let mut rng = Rng::default();
+    let mut left_count = 0;
+    for _ in 0..200 {
+      let left = vec![0; 10];
+      let right = vec![1; 10];
+      let merged = merge_samples(left, 900, right, 100, 10, &mut rng);
+      assert_eq!(merged.len(), 10);
+      left_count += merged.iter().filter(|value| **value == 0).count();
+    }
+    // left saw 90% of all values
+    assert!(left_count > 1650 && left_count < 1950);
+  }
+}
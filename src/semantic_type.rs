@@ -0,0 +1,274 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+pub static OBJECTID: &str = "object_id";
+pub static UUID: &str = "uuid";
+pub static EMAIL: &str = "email";
+pub static URL: &str = "url";
+pub static DATE: &str = "date";
+pub static IPV4: &str = "ipv4";
+pub static IPV6: &str = "ipv6";
+pub static PHONE: &str = "phone";
+pub static CREDIT_CARD: &str = "credit_card";
+
+/// Returns the semantic type of a string value, if it has a recognised format.
+/// Formats are checked from the most to the least specific one, so a value
+/// only gets one semantic type.
+pub fn get_semantic_type(string: &str) -> Option<&'static str> {
+  let checks: [(&'static str, fn(&str) -> bool); 9] = [
+    (OBJECTID, is_object_id),
+    (UUID, is_uuid),
+    (EMAIL, is_email),
+    (URL, is_url),
+    (DATE, is_iso_date),
+    (IPV4, |s| s.parse::<Ipv4Addr>().is_ok()),
+    (IPV6, |s| s.parse::<Ipv6Addr>().is_ok()),
+    (PHONE, is_e164_phone),
+    (CREDIT_CARD, is_credit_card),
+  ];
+  checks
+    .iter()
+    .find(|(_, check)| check(string))
+    .map(|(semantic_type, _)| *semantic_type)
+}
+
+fn is_hex(string: &str) -> bool {
+  string.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+fn is_digits(string: &str) -> bool {
+  !string.is_empty() && string.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+// A hex encoded ObjectId, i.e. `5031bb65fe4dce143635c960`.
+fn is_object_id(string: &str) -> bool {
+  string.len() == 24 && is_hex(string)
+}
+
+// i.e. `123e4567-e89b-12d3-a456-426614174000`
+fn is_uuid(string: &str) -> bool {
+  let groups: Vec<&str> = string.split('-').collect();
+  groups.len() == 5
+    && groups
+      .iter()
+      .map(|group| group.len())
+      .eq([8, 4, 4, 4, 12].iter().cloned())
+    && groups.iter().all(|group| is_hex(group))
+}
+
+fn is_email(string: &str) -> bool {
+  let mut parts = string.split('@');
+  let (local, domain) = match (parts.next(), parts.next(), parts.next()) {
+    (Some(local), Some(domain), None) => (local, domain),
+    _ => return false,
+  };
+  let domain_labels: Vec<&str> = domain.split('.').collect();
+  !local.is_empty()
+    && !local.chars().any(|c| c.is_whitespace() || c.is_control())
+    && domain_labels.len() > 1
+    && domain_labels.iter().all(|label| {
+      !label.is_empty()
+        && label
+          .chars()
+          .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    })
+}
+
+// An absolute URL with a scheme and an authority, i.e. `https://mongodb.com`.
+fn is_url(string: &str) -> bool {
+  let index = match string.find("://") {
+    Some(index) => index,
+    None => return false,
+  };
+  let (scheme, rest) = (&string[..index], &string[index + 3..]);
+  let mut scheme_chars = scheme.chars();
+  let valid_scheme = scheme_chars
+    .next()
+    .map_or(false, |c| c.is_ascii_alphabetic())
+    && scheme_chars.all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+  valid_scheme
+    && !rest.is_empty()
+    && !rest.starts_with('/')
+    && !rest.chars().any(|c| c.is_whitespace() || c.is_control())
+}
+
+// An ISO-8601 calendar date, optionally followed by a time and a time zone:
+// `2014-01-31`, `2014-01-31T22:26:33.000Z` or `2014-01-31T22:26+01:00`.
+fn is_iso_date(string: &str) -> bool {
+  let bytes = string.as_bytes();
+  if bytes.len() < 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+    return false;
+  }
+  let valid_date = string.get(..4).map_or(false, is_digits)
+    && string
+      .get(5..7)
+      .map_or(false, |month| is_in_range(month, 1, 12))
+    && string
+      .get(8..10)
+      .map_or(false, |day| is_in_range(day, 1, 31));
+  if !valid_date {
+    return false;
+  }
+  if bytes.len() == 10 {
+    return true;
+  }
+  if bytes[10] != b'T' && bytes[10] != b' ' {
+    return false;
+  }
+  is_iso_time(&string[11..])
+}
+
+fn is_iso_time(string: &str) -> bool {
+  // split off the time zone
+  let (time, zone) = match string.find(['Z', '+', '-']) {
+    Some(index) => string.split_at(index),
+    None => (string, ""),
+  };
+  let (time, fraction) = match time.find('.') {
+    Some(index) => (&time[..index], &time[index + 1..]),
+    None => (time, "0"),
+  };
+  let parts: Vec<&str> = time.split(':').collect();
+  let valid_time = (parts.len() == 2 || parts.len() == 3)
+    && is_in_range(parts[0], 0, 23)
+    && is_in_range(parts[1], 0, 59)
+    && parts
+      .get(2)
+      .map_or(true, |seconds| is_in_range(seconds, 0, 60))
+    && is_digits(fraction);
+  let valid_zone = match zone {
+    "" | "Z" => true,
+    _ => {
+      let offset = zone[1..].replace(':', "");
+      offset.len() == 4
+        && offset
+          .get(..2)
+          .map_or(false, |hours| is_in_range(hours, 0, 23))
+        && offset
+          .get(2..)
+          .map_or(false, |minutes| is_in_range(minutes, 0, 59))
+    }
+  };
+  valid_time && valid_zone
+}
+
+// Two digits within `min` and `max`.
+fn is_in_range(string: &str, min: u8, max: u8) -> bool {
+  string.len() == 2
+    && is_digits(string)
+    && string
+      .parse::<u8>()
+      .map_or(false, |num| (min..=max).contains(&num))
+}
+
+// An E.164 phone number: a plus sign followed by up to fifteen digits, i.e.
+// `+19786213180`.
+fn is_e164_phone(string: &str) -> bool {
+  string.starts_with('+')
+    && string.len() >= 9
+    && string.len() <= 16
+    && is_digits(&string[1..])
+    && !string[1..].starts_with('0')
+}
+
+// Thirteen to nineteen digits, optionally grouped by spaces or dashes, with a
+// valid Luhn checksum.
+fn is_credit_card(string: &str) -> bool {
+  if !string
+    .chars()
+    .all(|c| c.is_ascii_digit() || c == ' ' || c == '-')
+  {
+    return false;
+  }
+  let digits: Vec<u32> =
+    string.chars().filter_map(|c| c.to_digit(10)).collect();
+  if digits.len() < 13 || digits.len() > 19 {
+    return false;
+  }
+  let sum: u32 = digits
+    .iter()
+    .rev()
+    .enumerate()
+    .map(|(index, digit)| match index % 2 {
+      0 => *digit,
+      _ if *digit * 2 > 9 => *digit * 2 - 9,
+      _ => *digit * 2,
+    })
+    .sum();
+  sum % 10 == 0
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn it_gets_object_id() {
+    let object_id = get_semantic_type("5031bb65fe4dce143635c960");
+    assert_eq!(object_id, Some(OBJECTID));
+    assert_eq!(get_semantic_type("5031bb65fe4dce143635c96"), None);
+  }
+
+  #[test]
+  fn it_gets_uuid() {
+    let uuid = get_semantic_type("123e4567-e89b-12d3-a456-426614174000");
+    assert_eq!(uuid, Some(UUID));
+    let uuid = get_semantic_type("123e4567-e89b-12d3-a456-42661417400g");
+    assert_eq!(uuid, None);
+  }
+
+  #[test]
+  fn it_gets_email() {
+    let email = get_semantic_type("corefinder88@hotmail.com");
+    assert_eq!(email, Some(EMAIL));
+    assert_eq!(get_semantic_type("corefinder88@hotmail"), None);
+    assert_eq!(get_semantic_type("core finder@hotmail.com"), None);
+    assert_eq!(get_semantic_type("a@b@hotmail.com"), None);
+  }
+
+  #[test]
+  fn it_gets_url() {
+    let url = get_semantic_type("https://docs.rs/mongodb-schema-parser");
+    assert_eq!(url, Some(URL));
+    assert_eq!(get_semantic_type("mongodb+srv://cluster0"), Some(URL));
+    assert_eq!(get_semantic_type("docs.rs"), None);
+    assert_eq!(get_semantic_type("https://"), None);
+  }
+
+  #[test]
+  fn it_gets_date() {
+    assert_eq!(get_semantic_type("2014-01-31"), Some(DATE));
+    assert_eq!(get_semantic_type("2014-01-31T22:26:33.000Z"), Some(DATE));
+    assert_eq!(get_semantic_type("2014-01-31 22:26+01:00"), Some(DATE));
+    assert_eq!(get_semantic_type("2014-13-31"), None);
+    assert_eq!(get_semantic_type("2014-01-31T25:26"), None);
+    assert_eq!(get_semantic_type("2014-01-3é"), None);
+  }
+
+  #[test]
+  fn it_gets_ip() {
+    assert_eq!(get_semantic_type("192.168.0.1"), Some(IPV4));
+    assert_eq!(get_semantic_type("2001:db8::ff00:42:8329"), Some(IPV6));
+    assert_eq!(get_semantic_type("192.168.0.256"), None);
+  }
+
+  #[test]
+  fn it_gets_phone() {
+    assert_eq!(get_semantic_type("+19786213180"), Some(PHONE));
+    assert_eq!(get_semantic_type("+451634929422"), Some(PHONE));
+    assert_eq!(get_semantic_type("19786213180"), None);
+    assert_eq!(get_semantic_type("+1 978 621 3180"), None);
+  }
+
+  #[test]
+  fn it_gets_credit_card() {
+    assert_eq!(get_semantic_type("4111 1111 1111 1111"), Some(CREDIT_CARD));
+    assert_eq!(get_semantic_type("4111-1111-1111-1111"), Some(CREDIT_CARD));
+    assert_eq!(get_semantic_type("4111111111111112"), None);
+  }
+
+  #[test]
+  fn it_gets_none() {
+    assert_eq!(get_semantic_type("Ellie J Clarke"), None);
+    assert_eq!(get_semantic_type(""), None);
+  }
+}
@@ -0,0 +1,30 @@
+use std::cell::Cell;
+
+thread_local! {
+  static FULL_STATE: Cell<bool> = Cell::new(false);
+}
+
+/// Runs `f` with the full state of a SchemaParser being (de)serialized: the
+/// options and sketches of its fields, which are left out of its json
+/// output, as well as values tagged with their type.
+pub fn with_full_state<T>(f: impl FnOnce() -> T) -> T {
+  // restores the previous mode even if `f` panics
+  struct Reset(bool);
+  impl Drop for Reset {
+    fn drop(&mut self) {
+      FULL_STATE.with(|full_state| full_state.set(self.0));
+    }
+  }
+  let _reset = Reset(FULL_STATE.with(|full_state| full_state.replace(true)));
+  f()
+}
+
+pub fn is_full() -> bool {
+  FULL_STATE.with(Cell::get)
+}
+
+/// `skip_serializing_if` for fields only serialized as part of the full
+/// state. Those fields need `#[serde(default)]` to deserialize json output.
+pub fn skip<T>(_: &T) -> bool {
+  !is_full()
+}
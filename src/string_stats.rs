@@ -0,0 +1,168 @@
+use super::{semantic_type, LengthStats};
+use std::collections::BTreeMap;
+
+/// Statistics of String values: their lengths in characters, and how many of
+/// them belong to, or contain, a class of characters.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StringStats {
+  pub lengths: LengthStats,
+  /// Non-empty strings of ASCII digits only.
+  pub digits_only: usize,
+  /// Non-empty strings of ASCII letters only.
+  pub letters_only: usize,
+  /// Strings containing whitespace.
+  pub with_whitespace: usize,
+  /// Strings containing characters outside of ASCII.
+  pub with_non_ascii: usize,
+  /// Strings containing control characters.
+  pub with_control: usize,
+  /// Fraction of strings matching each recognised format, i.e. `email` or
+  /// `uuid`. Set when the field type is finalised.
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub semantic_types: BTreeMap<String, f32>,
+  #[serde(default, skip_serializing_if = "crate::state::skip")]
+  semantic_counts: BTreeMap<String, usize>,
+}
+
+impl StringStats {
+  pub fn new(string: &str) -> Self {
+    let mut stats = StringStats {
+      lengths: LengthStats::new(string.chars().count()),
+      digits_only: 0,
+      letters_only: 0,
+      with_whitespace: 0,
+      with_non_ascii: 0,
+      with_control: 0,
+      semantic_types: BTreeMap::new(),
+      semantic_counts: BTreeMap::new(),
+    };
+    stats.update_classes(string);
+    stats
+  }
+
+  pub fn update(&mut self, string: &str) {
+    self.lengths.update(string.chars().count());
+    self.update_classes(string);
+  }
+
+  pub fn merge(&mut self, other: &StringStats) {
+    self.lengths.merge(&other.lengths);
+    self.digits_only += other.digits_only;
+    self.letters_only += other.letters_only;
+    self.with_whitespace += other.with_whitespace;
+    self.with_non_ascii += other.with_non_ascii;
+    self.with_control += other.with_control;
+    for (semantic_type, count) in &other.semantic_counts {
+      *self
+        .semantic_counts
+        .entry(semantic_type.to_string())
+        .or_insert(0) += count;
+    }
+  }
+
+  pub fn finalise(&mut self) {
+    let total = self.lengths.count as f32;
+    self.semantic_types = self
+      .semantic_counts
+      .iter()
+      .map(|(semantic_type, count)| {
+        (semantic_type.to_string(), *count as f32 / total)
+      })
+      .collect();
+  }
+
+  fn update_classes(&mut self, string: &str) {
+    if !string.is_empty() {
+      if string.bytes().all(|byte| byte.is_ascii_digit()) {
+        self.digits_only += 1;
+      }
+      if string.bytes().all(|byte| byte.is_ascii_alphabetic()) {
+        self.letters_only += 1;
+      }
+    }
+    if string.chars().any(char::is_whitespace) {
+      self.with_whitespace += 1;
+    }
+    if !string.is_ascii() {
+      self.with_non_ascii += 1;
+    }
+    if string.chars().any(char::is_control) {
+      self.with_control += 1;
+    }
+    if let Some(semantic_type) = semantic_type::get_semantic_type(string) {
+      *self
+        .semantic_counts
+        .entry(semantic_type.to_string())
+        .or_insert(0) += 1;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn it_creates_new() {
+    let stats = StringStats::new("Nori");
+    assert_eq!(stats.lengths.min, 4);
+    assert_eq!(stats.letters_only, 1);
+    assert_eq!(stats.digits_only, 0);
+  }
+
+  #[test]
+  fn it_counts_length_in_characters() {
+    let stats = StringStats::new("Straße");
+    assert_eq!(stats.lengths.max, 6);
+    assert_eq!(stats.with_non_ascii, 1);
+    assert_eq!(stats.letters_only, 0);
+  }
+
+  #[test]
+  fn it_updates_classes() {
+    let mut stats = StringStats::new("50017");
+    stats.update("");
+    stats.update("133 Aloha Ave");
+    stats.update("line\nbreak");
+    assert_eq!(stats.lengths.count, 4);
+    assert_eq!(stats.lengths.min, 0);
+    assert_eq!(stats.digits_only, 1);
+    assert_eq!(stats.letters_only, 0);
+    assert_eq!(stats.with_whitespace, 2);
+    assert_eq!(stats.with_control, 1);
+  }
+
+  #[allow(clippy::float_cmp)]
+  #[test]
+  fn it_sets_semantic_types() {
+    let mut stats = StringStats::new("corefinder88@hotmail.com");
+    stats.update("bubbles51@gmail.com");
+    stats.update("+19786213180");
+    stats.update("n/a");
+    assert!(stats.semantic_types.is_empty());
+    stats.finalise();
+    assert_eq!(stats.semantic_types.len(), 2);
+    assert_eq!(stats.semantic_types["email"], 0.5);
+    assert_eq!(stats.semantic_types["phone"], 0.25);
+  }
+
+  #[allow(clippy::float_cmp)]
+  #[test]
+  fn it_merges() {
+    let mut stats = StringStats::new("Nori");
+    let other = StringStats::new("12345");
+    stats.merge(&other);
+    assert_eq!(stats.lengths.count, 2);
+    assert_eq!(stats.lengths.max, 5);
+    assert_eq!(stats.letters_only, 1);
+    assert_eq!(stats.digits_only, 1);
+    stats.finalise();
+    assert!(stats.semantic_types.is_empty());
+
+    let mut other = StringStats::new("netfreak22@msn.com");
+    other.update("bubbles51@gmail.com");
+    stats.merge(&other);
+    stats.finalise();
+    assert_eq!(stats.semantic_types["email"], 0.5);
+  }
+}
@@ -0,0 +1,236 @@
+use std::f64::consts::PI;
+
+// Trades accuracy for size: a digest keeps roughly this many centroids.
+const COMPRESSION: f64 = 100.0;
+// Number of values buffered before they are merged into the centroids.
+const BUFFER_SIZE: usize = 500;
+
+/// Approximate percentiles of a field type's values. Dates are given in
+/// milliseconds since the Unix epoch.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Percentiles {
+  pub p1: f64,
+  pub p25: f64,
+  pub p50: f64,
+  pub p75: f64,
+  pub p99: f64,
+}
+
+impl Percentiles {
+  pub fn from_digest(digest: &mut TDigest) -> Option<Self> {
+    Some(Percentiles {
+      p1: digest.quantile(0.01)?,
+      p25: digest.quantile(0.25)?,
+      p50: digest.quantile(0.5)?,
+      p75: digest.quantile(0.75)?,
+      p99: digest.quantile(0.99)?,
+    })
+  }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Centroid {
+  mean: f64,
+  weight: f64,
+}
+
+/// A merging t-digest, estimating quantiles of a stream of numbers in a
+/// bounded amount of memory. Values near the tails are kept most accurately.
+/// Two digests can be merged without losing accuracy.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct TDigest {
+  centroids: Vec<Centroid>,
+  buffer: Vec<f64>,
+  min: f64,
+  max: f64,
+}
+
+impl TDigest {
+  pub fn insert(&mut self, num: f64) {
+    if self.is_empty() {
+      self.min = num;
+      self.max = num;
+    }
+    self.min = self.min.min(num);
+    self.max = self.max.max(num);
+    self.buffer.push(num);
+    if self.buffer.len() >= BUFFER_SIZE {
+      self.compress();
+    }
+  }
+
+  pub fn merge(&mut self, other: &TDigest) {
+    if other.is_empty() {
+      return;
+    }
+    if self.is_empty() {
+      *self = other.clone();
+      return;
+    }
+    self.min = self.min.min(other.min);
+    self.max = self.max.max(other.max);
+    self.centroids.extend(other.centroids.iter().cloned());
+    self.buffer.extend(&other.buffer);
+    self.compress();
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.centroids.is_empty() && self.buffer.is_empty()
+  }
+
+  /// Estimated value at quantile `q`, where `q` is in `[0, 1]`.
+  pub fn quantile(&mut self, q: f64) -> Option<f64> {
+    if !self.buffer.is_empty() {
+      self.compress();
+    }
+    let centroids = &self.centroids;
+    match centroids.len() {
+      0 => return None,
+      1 => return Some(centroids[0].mean),
+      _ => (),
+    }
+
+    let total: f64 = centroids.iter().map(|c| c.weight).sum();
+    let target = q * total;
+
+    // between the smallest value and the center of the first centroid
+    let first = &centroids[0];
+    if target < first.weight / 2.0 {
+      let t = target / (first.weight / 2.0);
+      return Some(self.min + t * (first.mean - self.min));
+    }
+
+    let mut cumulative = 0.0;
+    for pair in centroids.windows(2) {
+      let left_center = cumulative + pair[0].weight / 2.0;
+      let right_center = cumulative + pair[0].weight + pair[1].weight / 2.0;
+      if target <= right_center {
+        let t = (target - left_center) / (right_center - left_center);
+        return Some(pair[0].mean + t * (pair[1].mean - pair[0].mean));
+      }
+      cumulative += pair[0].weight;
+    }
+
+    // between the center of the last centroid and the largest value
+    let last = &centroids[centroids.len() - 1];
+    let t = (target - (total - last.weight / 2.0)) / (last.weight / 2.0);
+    Some((last.mean + t * (self.max - last.mean)).min(self.max))
+  }
+
+  fn compress(&mut self) {
+    let mut centroids = std::mem::take(&mut self.centroids);
+    centroids.extend(self.buffer.drain(..).map(|num| Centroid {
+      mean: num,
+      weight: 1.0,
+    }));
+    centroids.sort_by(|a, b| a.mean.total_cmp(&b.mean));
+    let total: f64 = centroids.iter().map(|c| c.weight).sum();
+
+    let mut centroids = centroids.into_iter();
+    let mut current = match centroids.next() {
+      Some(centroid) => centroid,
+      None => return,
+    };
+    let mut weight_so_far = current.weight;
+    let mut limit = total * Self::q_limit(0.0);
+    for next in centroids {
+      let weight = next.weight;
+      if weight_so_far + weight <= limit {
+        current.mean +=
+          (next.mean - current.mean) * weight / (current.weight + weight);
+        current.weight += weight;
+      } else {
+        self.centroids.push(current);
+        limit = total * Self::q_limit(weight_so_far / total);
+        current = next;
+      }
+      weight_so_far += weight;
+    }
+    self.centroids.push(current);
+  }
+
+  // Largest quantile a centroid starting at quantile `q` may reach, using the
+  // scale function k(q) = δ / 2π * asin(2q - 1): a centroid can span at most
+  // one unit of k, so centroids near the tails stay small.
+  fn q_limit(q: f64) -> f64 {
+    let k = COMPRESSION / (2.0 * PI) * (2.0 * q - 1.0).asin() + 1.0;
+    let angle = k * 2.0 * PI / COMPRESSION;
+    if angle >= PI / 2.0 {
+      1.0
+    } else {
+      (angle.sin() + 1.0) / 2.0
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+    assert!(
+      (actual - expected).abs() <= tolerance,
+      "{} is not within {} of {}",
+      actual,
+      tolerance,
+      expected
+    );
+  }
+
+  #[test]
+  fn it_has_no_quantile_when_empty() {
+    let mut digest = TDigest::default();
+    assert_eq!(digest.quantile(0.5), None);
+  }
+
+  #[allow(clippy::float_cmp)]
+  #[test]
+  fn it_gets_quantile_of_single_value() {
+    let mut digest = TDigest::default();
+    digest.insert(42.0);
+    assert_eq!(digest.quantile(0.01), Some(42.0));
+    assert_eq!(digest.quantile(0.99), Some(42.0));
+  }
+
+  #[test]
+  fn it_gets_quantiles() {
+    let mut digest = TDigest::default();
+    // insert out of order, so values don't arrive sorted
+    for i in 0..10_000 {
+      digest.insert(f64::from((i * 7_919) % 10_000));
+    }
+    assert!(digest.centroids.len() < 200);
+    assert_close(digest.quantile(0.01).unwrap(), 100.0, 10.0);
+    assert_close(digest.quantile(0.25).unwrap(), 2_500.0, 50.0);
+    assert_close(digest.quantile(0.5).unwrap(), 5_000.0, 50.0);
+    assert_close(digest.quantile(0.75).unwrap(), 7_500.0, 50.0);
+    assert_close(digest.quantile(0.99).unwrap(), 9_900.0, 10.0);
+  }
+
+  #[test]
+  fn it_gets_percentiles() {
+    let mut digest = TDigest::default();
+    assert_eq!(Percentiles::from_digest(&mut digest), None);
+    for i in 0..=100 {
+      digest.insert(f64::from(i));
+    }
+    let percentiles = Percentiles::from_digest(&mut digest).unwrap();
+    assert_close(percentiles.p1, 1.0, 0.5);
+    assert_close(percentiles.p50, 50.0, 1.0);
+    assert_close(percentiles.p99, 99.0, 0.5);
+  }
+
+  #[test]
+  fn it_merges() {
+    let mut digest = TDigest::default();
+    let mut other = TDigest::default();
+    for i in 0..5_000 {
+      digest.insert(f64::from(i));
+      other.insert(f64::from(i + 5_000));
+    }
+    digest.merge(&other);
+    assert_close(digest.quantile(0.01).unwrap(), 100.0, 10.0);
+    assert_close(digest.quantile(0.5).unwrap(), 5_000.0, 50.0);
+    assert_close(digest.quantile(0.99).unwrap(), 9_900.0, 10.0);
+  }
+}
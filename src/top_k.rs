@@ -0,0 +1,214 @@
+use super::ValueType;
+use std::cmp::Reverse;
+
+/// A frequently seen value and how often it was seen. Counts are exact as long
+/// as no more distinct values than tracked candidates were seen, otherwise
+/// they may be overestimated.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TopValue {
+  pub value: ValueType,
+  pub count: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Counter {
+  value: ValueType,
+  hash: u64,
+  count: usize,
+}
+
+/// Space-saving heavy hitters: tracks a bounded number of candidate values.
+/// Once full, a new value replaces the least frequent candidate and inherits
+/// its count, so values seen often are never lost.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct SpaceSaving {
+  capacity: usize,
+  counters: Vec<Counter>,
+}
+
+impl SpaceSaving {
+  pub fn new(capacity: usize) -> Self {
+    SpaceSaving {
+      capacity,
+      counters: Vec::new(),
+    }
+  }
+
+  /// Counts a value, given its `ValueType::stable_hash`.
+  pub fn insert(&mut self, value: &ValueType, hash: u64) {
+    if self.capacity == 0 {
+      return;
+    }
+    let existing = self
+      .counters
+      .iter_mut()
+      .find(|counter| counter.hash == hash && counter.value == *value);
+    if let Some(counter) = existing {
+      counter.count += 1;
+    } else if self.counters.len() < self.capacity {
+      self.counters.push(Counter {
+        value: value.clone(),
+        hash,
+        count: 1,
+      });
+    } else if let Some(min) =
+      self.counters.iter_mut().min_by_key(|counter| counter.count)
+    {
+      min.value = value.clone();
+      min.hash = hash;
+      min.count += 1;
+    }
+  }
+
+  /// Merges the candidates of another sketch. A value only one sketch is
+  /// tracking may have been seen by the other as often as that sketch's least
+  /// frequent candidate, so that count is added to keep counts overestimated
+  /// rather than underestimated.
+  pub fn merge(&mut self, other: &SpaceSaving) {
+    let min = self.min_count();
+    let other_min = other.min_count();
+    for counter in &mut self.counters {
+      counter.count += other
+        .find(counter)
+        .map_or(other_min, |other_counter| other_counter.count);
+    }
+    for other_counter in &other.counters {
+      if self.find(other_counter).is_none() {
+        self.counters.push(Counter {
+          count: other_counter.count + min,
+          ..other_counter.clone()
+        });
+      }
+    }
+    if self.counters.len() > self.capacity {
+      self.counters.sort_by_key(|counter| Reverse(counter.count));
+      self.counters.truncate(self.capacity);
+    }
+  }
+
+  // Count of the least frequent candidate once every counter is in use. Until
+  // then, values that aren't candidates haven't been seen at all.
+  fn min_count(&self) -> usize {
+    if self.counters.len() < self.capacity {
+      return 0;
+    }
+    self
+      .counters
+      .iter()
+      .map(|counter| counter.count)
+      .min()
+      .unwrap_or(0)
+  }
+
+  fn find(&self, counter: &Counter) -> Option<&Counter> {
+    self
+      .counters
+      .iter()
+      .find(|c| c.hash == counter.hash && c.value == counter.value)
+  }
+
+  /// The `k` most frequent values, most frequent first.
+  pub fn top(&self, k: usize) -> Vec<TopValue> {
+    let mut counters: Vec<&Counter> = self.counters.iter().collect();
+    counters.sort_by_key(|counter| Reverse(counter.count));
+    counters
+      .into_iter()
+      .take(k)
+      .map(|counter| TopValue {
+        value: counter.value.clone(),
+        count: counter.count,
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn insert_str(top_k: &mut SpaceSaving, string: &str) {
+    let value = ValueType::Str(string.to_string());
+    let hash = value.stable_hash();
+    top_k.insert(&value, hash);
+  }
+
+  #[test]
+  fn it_counts_values() {
+    let mut top_k = SpaceSaving::new(4);
+    for string in &["cat", "dog", "cat", "bird", "cat", "dog"] {
+      insert_str(&mut top_k, string);
+    }
+    let top = top_k.top(2);
+    assert_eq!(top.len(), 2);
+    assert_eq!(top[0].value, ValueType::Str("cat".to_string()));
+    assert_eq!(top[0].count, 3);
+    assert_eq!(top[1].value, ValueType::Str("dog".to_string()));
+    assert_eq!(top[1].count, 2);
+  }
+
+  #[test]
+  fn it_keeps_frequent_values_when_full() {
+    let mut top_k = SpaceSaving::new(2);
+    for i in 0..100 {
+      insert_str(&mut top_k, "cat");
+      insert_str(&mut top_k, &i.to_string());
+    }
+    let top = top_k.top(1);
+    assert_eq!(top[0].value, ValueType::Str("cat".to_string()));
+    assert_eq!(top[0].count, 100);
+  }
+
+  #[test]
+  fn it_is_disabled_without_capacity() {
+    let mut top_k = SpaceSaving::new(0);
+    insert_str(&mut top_k, "cat");
+    assert!(top_k.top(10).is_empty());
+  }
+
+  #[test]
+  fn it_merges() {
+    let mut top_k = SpaceSaving::new(2);
+    let mut other = SpaceSaving::new(2);
+    insert_str(&mut top_k, "cat");
+    insert_str(&mut top_k, "dog");
+    insert_str(&mut other, "cat");
+    insert_str(&mut other, "bird");
+    top_k.merge(&other);
+    let top = top_k.top(10);
+    assert_eq!(top.len(), 2);
+    assert_eq!(top[0].value, ValueType::Str("cat".to_string()));
+    assert_eq!(top[0].count, 2);
+  }
+
+  #[test]
+  fn it_adds_min_count_to_missing_values_when_merging() {
+    let mut top_k = SpaceSaving::new(2);
+    let mut other = SpaceSaving::new(2);
+    for string in &["cat", "cat", "cat", "dog"] {
+      insert_str(&mut top_k, string);
+    }
+    for string in &["bird", "bird", "cat"] {
+      insert_str(&mut other, string);
+    }
+    top_k.merge(&other);
+    let top = top_k.top(10);
+    assert_eq!(top.len(), 2);
+    assert_eq!(top[0].value, ValueType::Str("cat".to_string()));
+    assert_eq!(top[0].count, 4);
+    // top_k may have seen "bird" as often as its least frequent candidate
+    assert_eq!(top[1].value, ValueType::Str("bird".to_string()));
+    assert_eq!(top[1].count, 3);
+  }
+
+  #[test]
+  fn it_merges_exact_counts_when_not_full() {
+    let mut top_k = SpaceSaving::new(4);
+    let mut other = SpaceSaving::new(4);
+    insert_str(&mut top_k, "cat");
+    insert_str(&mut other, "dog");
+    top_k.merge(&other);
+    let top = top_k.top(10);
+    assert_eq!(top.len(), 2);
+    assert!(top.iter().all(|top_value| top_value.count == 1));
+  }
+}
@@ -1,5 +1,11 @@
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd)]
-#[serde(untagged)]
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+
+/// Values are serialized without their type, i.e. `"Nori"` or `1`, except in
+/// a SchemaParser's full state, which needs to tell apart values such as an
+/// Int and a Long, or a String and a Decimal128.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum ValueType {
   Str(String),
   I32(i32),
@@ -8,5 +14,178 @@ pub enum ValueType {
   FloatingPoint(f64),
   Binary(Vec<u8>),
   Boolean(bool),
+  UtcDatetime(DateTime<Utc>),
   Null(String),
+  // hex string of the ObjectId
+  ObjectId(String),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "ValueType", untagged)]
+enum UntaggedValue {
+  Str(String),
+  I32(i32),
+  I64(i64),
+  Decimal128(String),
+  FloatingPoint(f64),
+  Binary(Vec<u8>),
+  Boolean(bool),
+  UtcDatetime(DateTime<Utc>),
+  Null(String),
+  ObjectId(String),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "ValueType")]
+enum TaggedValue {
+  Str(String),
+  I32(i32),
+  I64(i64),
+  Decimal128(String),
+  // json has no NaN or infinity, so the state keeps the bits of the double
+  FloatingPoint(#[serde(with = "float_bits")] f64),
+  Binary(Vec<u8>),
+  Boolean(bool),
+  UtcDatetime(DateTime<Utc>),
+  Null(String),
+  ObjectId(String),
+}
+
+mod float_bits {
+  use serde::{Deserialize, Deserializer, Serializer};
+
+  pub fn serialize<S: Serializer>(num: &f64, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_u64(num.to_bits())
+  }
+
+  pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<f64, D::Error> {
+    Ok(f64::from_bits(u64::deserialize(d)?))
+  }
+}
+
+impl Serialize for ValueType {
+  fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+    if crate::state::is_full() {
+      TaggedValue::serialize(self, s)
+    } else {
+      UntaggedValue::serialize(self, s)
+    }
+  }
+}
+
+impl<'de> Deserialize<'de> for ValueType {
+  fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+    if crate::state::is_full() {
+      TaggedValue::deserialize(d)
+    } else {
+      UntaggedValue::deserialize(d)
+    }
+  }
+}
+
+impl ValueType {
+  /// Orders values like `partial_cmp`, but orders FloatingPoint values with
+  /// `f64::total_cmp` so that NaN can be sorted as well. Like `stable_hash`,
+  /// this tells apart `0.0` and `-0.0`.
+  pub fn total_cmp(&self, other: &Self) -> Ordering {
+    match (self, other) {
+      (ValueType::FloatingPoint(num), ValueType::FloatingPoint(other)) => {
+        num.total_cmp(other)
+      }
+      // only floating point values have no order among themselves
+      _ => self.partial_cmp(other).unwrap(),
+    }
+  }
+
+  /// Returns a 64-bit hash of the value for sketches such as HyperLogLog.
+  /// Unlike `std::hash::Hash` it covers floating point values, and it is
+  /// stable across runs and platforms so sketches can be merged.
+  pub fn stable_hash(&self) -> u64 {
+    match self {
+      ValueType::Str(string) => hash_bytes(0, string.as_bytes()),
+      ValueType::I32(num) => hash_bytes(1, &num.to_le_bytes()),
+      ValueType::I64(num) => hash_bytes(2, &num.to_le_bytes()),
+      ValueType::Decimal128(d128) => hash_bytes(3, d128.as_bytes()),
+      ValueType::FloatingPoint(num) => {
+        hash_bytes(4, &num.to_bits().to_le_bytes())
+      }
+      ValueType::Binary(vec) => hash_bytes(5, vec),
+      ValueType::Boolean(boolean) => hash_bytes(6, &[*boolean as u8]),
+      ValueType::Null(_) => hash_bytes(7, &[]),
+      ValueType::UtcDatetime(date) => {
+        hash_bytes(8, &date.timestamp_millis().to_le_bytes())
+      }
+      ValueType::ObjectId(id) => hash_bytes(9, id.as_bytes()),
+    }
+  }
+}
+
+// FNV-1a, followed by murmur3's finalizer so that the high bits are as well
+// distributed as the low ones.
+fn hash_bytes(tag: u8, bytes: &[u8]) -> u64 {
+  let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+  for byte in Some(&tag).into_iter().chain(bytes) {
+    hash ^= u64::from(*byte);
+    hash = hash.wrapping_mul(0x0100_0000_01b3);
+  }
+  hash ^= hash >> 33;
+  hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+  hash ^= hash >> 33;
+  hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+  hash ^ (hash >> 33)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::SchemaParser;
+
+  #[test]
+  fn it_hashes_equal_values_equally() {
+    let hash = ValueType::Str("Nori".to_string()).stable_hash();
+    assert_eq!(hash, ValueType::Str("Nori".to_string()).stable_hash());
+    assert_ne!(hash, ValueType::Str("Rey".to_string()).stable_hash());
+  }
+
+  #[test]
+  fn it_orders_nan() {
+    let nan = ValueType::FloatingPoint(f64::NAN);
+    assert_eq!(nan.total_cmp(&nan), Ordering::Equal);
+    assert_eq!(
+      ValueType::FloatingPoint(1.0).total_cmp(&nan),
+      Ordering::Less
+    );
+    assert_eq!(
+      ValueType::I32(1).total_cmp(&ValueType::I32(2)),
+      Ordering::Less
+    );
+  }
+
+  #[test]
+  fn it_flushes_nan_values() {
+    let mut schema_parser = SchemaParser::new();
+    let json = r#"{"weight": {"$numberDouble": "NaN"}}"#;
+    schema_parser.write_json(json).unwrap();
+    schema_parser.write_json(json).unwrap();
+    let schema = schema_parser.flush();
+    let weight = &schema.fields["weight"].types["Double"];
+    assert_eq!(weight.unique, Some(1));
+    assert!(weight.has_duplicates);
+  }
+
+  #[test]
+  fn it_hashes_types_differently() {
+    let string = ValueType::Str("1".to_string()).stable_hash();
+    let decimal = ValueType::Decimal128("1".to_string()).stable_hash();
+    assert_ne!(string, decimal);
+    assert_ne!(
+      ValueType::I32(1).stable_hash(),
+      ValueType::I64(1).stable_hash()
+    );
+    let hex = "5d3a1e4a2f1b0c0001a1b2c3".to_string();
+    assert_ne!(
+      ValueType::Str(hex.clone()).stable_hash(),
+      ValueType::ObjectId(hex).stable_hash()
+    );
+  }
 }
@@ -1,6 +1,17 @@
 use failure::Error;
 use mongodb_schema_parser::SchemaParser;
-use std::fs;
+use std::fs::{self, File};
+use std::io::BufReader;
+
+// Flushed schema of every document of the fanclub example.
+fn fanclub_schema() -> Result<SchemaParser, Error> {
+  let file = fs::read_to_string("examples/fanclub.json")?;
+  let mut schema_parser = SchemaParser::new();
+  for json in file.trim().split('\n') {
+    schema_parser.write_json(json)?;
+  }
+  Ok(schema_parser.flush())
+}
 
 #[test]
 fn json_file_gen() -> Result<(), Error> {
@@ -16,3 +27,133 @@ fn json_file_gen() -> Result<(), Error> {
   println!("{:?}", schema);
   Ok(())
 }
+
+#[test]
+fn json_file_merge() -> Result<(), Error> {
+  let file = fs::read_to_string("examples/fanclub.json")?;
+  let vec: Vec<&str> = file.trim().split('\n').collect();
+  let mut schema_parser = SchemaParser::new();
+  for json in &vec {
+    schema_parser.write_json(&json)?;
+  }
+
+  let (first, second) = vec.split_at(vec.len() / 2);
+  let mut merged = SchemaParser::new();
+  for json in first {
+    merged.write_json(&json)?;
+  }
+  let mut other = SchemaParser::new();
+  for json in second {
+    other.write_json(&json)?;
+  }
+  merged.merge(other);
+
+  // statistics over floating point values can differ in their last digits,
+  // since merging adds them up in a different order; compare counts instead.
+  let merged = merged.flush();
+  let schema = schema_parser.flush();
+  let merged_fields = merged.flat_fields();
+  let fields = schema.flat_fields();
+  assert_eq!(
+    merged_fields.keys().collect::<Vec<_>>(),
+    fields.keys().collect::<Vec<_>>()
+  );
+  for (path, field) in fields {
+    let merged_field = &merged_fields[&path];
+    assert_eq!(merged_field.count, field.count);
+    assert_eq!(merged_field.bson_types, field.bson_types);
+    for (bson_type, field_type) in &field.types {
+      let merged_field_type = &merged_field.types[bson_type];
+      assert_eq!(merged_field_type.count, field_type.count);
+      assert_eq!(merged_field_type.values, field_type.values);
+      assert_eq!(merged_field_type.unique, field_type.unique);
+    }
+  }
+  Ok(())
+}
+
+#[test]
+fn json_file_enums() -> Result<(), Error> {
+  let schema = fanclub_schema()?;
+  let status = &schema.get_field("membership_status").unwrap().types["String"];
+  let enum_values = status.enum_values.as_ref().unwrap();
+  assert_eq!(enum_values.len(), 3);
+  assert_eq!(enum_values["ACTIVE"], 72);
+
+  let gender = &schema.get_field("gender").unwrap().types["String"];
+  assert_eq!(gender.enum_values.as_ref().unwrap().len(), 2);
+
+  let name = &schema.get_field("name").unwrap().types["String"];
+  assert!(name.enum_values.is_none());
+  Ok(())
+}
+
+#[allow(clippy::float_cmp)]
+#[test]
+fn json_file_semantic_types() -> Result<(), Error> {
+  let schema = fanclub_schema()?;
+  let email = &schema.get_field("email").unwrap().types["String"];
+  let email_stats = email.string_stats.as_ref().unwrap();
+  assert_eq!(email_stats.semantic_types["email"], 1.0);
+
+  let phone_no = &schema.get_field("phone_no").unwrap().types["String"];
+  let phone_no_stats = phone_no.string_stats.as_ref().unwrap();
+  assert_eq!(phone_no_stats.semantic_types["phone"], 1.0);
+  Ok(())
+}
+
+#[test]
+fn json_file_object_ids() -> Result<(), Error> {
+  let schema = fanclub_schema()?;
+  let id = &schema.get_field("_id").unwrap().types["ObjectId"];
+  let stats = id.object_id_stats.as_ref().unwrap();
+  // 2012-08-20T01:36:17Z to 2012-08-29T22:09:15Z
+  assert_eq!(stats.min_timestamp.timestamp(), 1_345_426_577);
+  assert_eq!(stats.max_timestamp.timestamp(), 1_346_278_155);
+  assert!(stats.monotonic);
+  Ok(())
+}
+
+#[allow(clippy::float_cmp)]
+#[test]
+fn json_file_geo_json() -> Result<(), Error> {
+  let schema = fanclub_schema()?;
+  let location =
+    &schema.get_field("address.location").unwrap().types["Document"];
+  let geo = location.geo.as_ref().unwrap();
+  assert_eq!(geo.count, 100);
+  assert_eq!(geo.types["Point"], 100);
+  let [min_lng, min_lat, max_lng, max_lat] = geo.bbox.unwrap();
+  assert_eq!(min_lng, -122.69747685630946);
+  assert_eq!(min_lat, 27.53698988541721);
+  assert_eq!(max_lng, -71.00580541060702);
+  assert_eq!(max_lat, 45.557548287657134);
+  Ok(())
+}
+
+#[test]
+fn json_file_extended_json() -> Result<(), Error> {
+  let schema = fanclub_schema()?;
+  let last_login = schema.get_field("last_login").unwrap();
+  assert_eq!(last_login.bson_types, vec!["UtcDatetime"]);
+  let phone_no = schema.get_field("phone_no").unwrap();
+  assert!(phone_no.types.contains_key("Long"));
+  assert!(!phone_no.types.contains_key("Document"));
+  // relaxed json numbers are Int when they fit
+  let age = schema.get_field("age").unwrap();
+  assert_eq!(age.types["Int"].count, 58);
+  assert!(!age.types.contains_key("Long"));
+  Ok(())
+}
+
+#[test]
+fn ndjson_file_reader() -> Result<(), Error> {
+  let file = BufReader::new(File::open("examples/fanclub.json")?);
+  let mut schema_parser = SchemaParser::new();
+  let mut errors = Vec::new();
+  schema_parser.write_ndjson_reader(file, |error| errors.push(error))?;
+  assert!(errors.is_empty());
+  let schema = schema_parser.flush();
+  assert_eq!(schema.get_field("_id").unwrap().count, 100);
+  Ok(())
+}